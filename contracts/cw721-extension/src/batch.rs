@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::{Binary, DepsMut, Env, Event, MessageInfo, Response, Storage};
+use cw721_base::MintMsg;
+
+use crate::{
+    error::ContractError,
+    execute::execute_base,
+    helpers::ensure_minter,
+    soulbound::ensure_not_soulbound,
+    state::{Cw721MetadataContract, Extension, CONFIG},
+    supply::ensure_supply,
+    validation::validate_metadata,
+};
+
+/// Maximum batch size if none is given on instantiation.
+pub const DEFAULT_MAX_BATCH_SIZE: u32 = 50;
+
+fn ensure_batch_size(storage: &dyn Storage, size: usize) -> Result<(), ContractError> {
+    let max = CONFIG.load(storage)?.max_batch_size;
+    if size > max as usize {
+        return Err(ContractError::BatchTooLarge { size, max });
+    }
+    Ok(())
+}
+
+/// Names the token an error is about.
+fn for_token(token_id: &str) -> impl Fn(ContractError) -> ContractError + '_ {
+    move |error| ContractError::BatchToken {
+        token_id: token_id.to_string(),
+        error: Box::new(error),
+    }
+}
+
+/// Checks that all transfers of `token_ids` by the sender would pass.
+fn validate_transfers(
+    deps: &DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    recipient: &str,
+    token_ids: &[String],
+) -> Result<(), ContractError> {
+    ensure_batch_size(deps.storage, token_ids.len())?;
+    deps.api.addr_validate(recipient)?;
+    let contract = Cw721MetadataContract::default();
+    let mut seen = HashSet::new();
+    for token_id in token_ids {
+        if !seen.insert(token_id) {
+            return Err(ContractError::DuplicateToken {
+                token_id: token_id.clone(),
+            });
+        }
+        let token = contract
+            .tokens
+            .load(deps.storage, token_id)
+            .map_err(|err| for_token(token_id)(err.into()))?;
+        contract
+            .check_can_send(deps.as_ref(), env, info, &token)
+            .map_err(|err| for_token(token_id)(err.into()))?;
+        ensure_not_soulbound(deps.storage, token_id, &token.extension)?;
+    }
+    Ok(())
+}
+
+pub fn batch_mint(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mints: Vec<MintMsg<Extension>>,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    ensure_batch_size(deps.storage, mints.len())?;
+    ensure_supply(deps.storage, mints.len() as u64)?;
+    let tokens = Cw721MetadataContract::default().tokens;
+    let mut seen = HashSet::new();
+    for mint in &mints {
+        let token_id = mint.token_id.as_str();
+        if !seen.insert(token_id) || tokens.has(deps.storage, token_id) {
+            return Err(for_token(token_id)(
+                cw721_base::ContractError::Claimed {}.into(),
+            ));
+        }
+        deps.api
+            .addr_validate(&mint.owner)
+            .map_err(|err| for_token(token_id)(err.into()))?;
+        validate_metadata(deps.as_ref(), &mint.extension).map_err(for_token(token_id))?;
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_mint")
+        .add_attribute("minter", info.sender.clone())
+        .add_attribute("count", mints.len().to_string());
+    for mint in mints {
+        let event = Event::new("mint")
+            .add_attribute("token_id", &mint.token_id)
+            .add_attribute("owner", &mint.owner);
+        let token_id = mint.token_id.clone();
+        let msg = cw721_base::ExecuteMsg::Mint(mint);
+        execute_base(deps.branch(), env.clone(), info.clone(), msg)
+            .map_err(for_token(&token_id))?;
+        res = res.add_event(event);
+    }
+    Ok(res)
+}
+
+pub fn batch_transfer_nft(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    validate_transfers(&deps, &env, &info, &recipient, &token_ids)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_transfer_nft")
+        .add_attribute("sender", info.sender.clone())
+        .add_attribute("recipient", &recipient)
+        .add_attribute("count", token_ids.len().to_string());
+    for token_id in token_ids {
+        let msg = cw721_base::ExecuteMsg::TransferNft {
+            recipient: recipient.clone(),
+            token_id: token_id.clone(),
+        };
+        execute_base(deps.branch(), env.clone(), info.clone(), msg)
+            .map_err(for_token(&token_id))?;
+        res = res.add_event(Event::new("transfer").add_attribute("token_id", token_id));
+    }
+    Ok(res)
+}
+
+pub fn batch_send_nft(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_ids: Vec<String>,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    validate_transfers(&deps, &env, &info, &contract, &token_ids)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "batch_send_nft")
+        .add_attribute("sender", info.sender.clone())
+        .add_attribute("recipient", &contract)
+        .add_attribute("count", token_ids.len().to_string());
+    for token_id in token_ids {
+        let send = cw721_base::ExecuteMsg::SendNft {
+            contract: contract.clone(),
+            token_id: token_id.clone(),
+            msg: msg.clone(),
+        };
+        let sent = execute_base(deps.branch(), env.clone(), info.clone(), send)
+            .map_err(for_token(&token_id))?;
+        res = res
+            .add_submessages(sent.messages)
+            .add_event(Event::new("send").add_attribute("token_id", token_id));
+    }
+    Ok(res)
+}
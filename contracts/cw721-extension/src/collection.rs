@@ -0,0 +1,52 @@
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Response, StdResult};
+
+use crate::{
+    error::ContractError,
+    helpers::ensure_creator,
+    msg::CollectionInfoResponse,
+    royalties::validate_percentage,
+    state::{CollectionInfo, COLLECTION_INFO, CONFIG},
+    validation::validate_collection_fields,
+};
+
+/// Checks the links and the default royalties of the collection.
+pub fn validate_collection_info(
+    deps: Deps,
+    collection_info: &CollectionInfo,
+    max_royalty_percentage: u64,
+) -> Result<(), ContractError> {
+    validate_collection_fields(collection_info)?;
+    if let Some(percentage) = collection_info.royalty_percentage {
+        validate_percentage(percentage, max_royalty_percentage)?;
+    }
+    if let Some(address) = &collection_info.royalty_payment_address {
+        deps.api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+pub fn update_collection_info(
+    deps: DepsMut,
+    info: MessageInfo,
+    collection_info: CollectionInfo,
+) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    validate_collection_info(
+        deps.as_ref(),
+        &collection_info,
+        config.max_royalty_percentage,
+    )?;
+    COLLECTION_INFO.save(deps.storage, &collection_info)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_collection_info")
+        .add_attribute("sender", info.sender))
+}
+
+pub fn query_collection_info(deps: Deps) -> StdResult<CollectionInfoResponse> {
+    Ok(CollectionInfoResponse {
+        creator: CONFIG.load(deps.storage)?.creator.into_string(),
+        collection_info: COLLECTION_INFO.load(deps.storage)?,
+    })
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +6,84 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Base(#[from] cw721_base::ContractError),
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
+
+    #[error("royalty percentage ({percentage}) exceeds the maximum ({max})")]
+    RoyaltyTooHigh { percentage: u64, max: u64 },
+
+    #[error("metadata of token ({token_id}) is frozen")]
+    MetadataFrozen { token_id: String },
+
+    #[error("{field} ({uri}) must start with ipfs://, ar:// or https://")]
+    InvalidUriScheme { field: String, uri: String },
+
+    #[error("background color ({color}) must be six hex digits")]
+    InvalidBackgroundColor { color: String },
+
+    #[error("trait type ({trait_type}) appears more than once in the attributes")]
+    DuplicateTraitType { trait_type: String },
+
+    #[error("{field} exceeds the maximum length ({max})")]
+    StringTooLong { field: String, max: usize },
+
+    #[error("{count} attributes exceed the maximum ({max})")]
+    TooManyAttributes { count: usize, max: usize },
+
+    #[error("token ({token_id}) is soulbound and can not be transferred or approved")]
+    Soulbound { token_id: String },
+
+    #[error("tokens of a soulbound collection can not be approved")]
+    SoulboundCollection {},
+
+    #[error("token ({token_id}) is not soulbound")]
+    NotSoulbound { token_id: String },
+
+    #[error("soulbound flag of token ({token_id}) can not be updated")]
+    SoulboundChanged { token_id: String },
+
+    #[error("maximum supply ({max_supply}) reached")]
+    MaxSupplyReached { max_supply: u64 },
+
+    #[error("no mint phase is open")]
+    NoMintPhase {},
+
+    #[error("{address} is not on the allowlist")]
+    NotAllowlisted { address: String },
+
+    #[error("mint limit ({limit}) of phase {phase} reached")]
+    MintLimitReached { phase: String, limit: u32 },
+
+    #[error("mint costs {price}")]
+    InsufficientFunds { price: Coin },
+
+    #[error("mint phase {name} ends before it starts")]
+    InvalidMintPhase { name: String },
+
+    #[error("mint phase {name} appears more than once")]
+    DuplicateMintPhase { name: String },
+
+    #[error("price ({price}) of mint phase {name} must be a positive amount of a valid denom")]
+    InvalidMintPrice { name: String, price: Coin },
+
+    #[error("mint phase {name} must allow at least one mint per address")]
+    InvalidMintLimit { name: String },
+
+    #[error("mint phase {name} overlaps with the one before")]
+    MintPhasesOverlap { name: String },
+
+    #[error("batch of {size} tokens exceeds the maximum ({max})")]
+    BatchTooLarge { size: usize, max: u32 },
+
+    #[error("token ({token_id}) appears more than once in the batch")]
+    DuplicateToken { token_id: String },
+
+    #[error("token ({token_id}): {error}")]
+    BatchToken {
+        token_id: String,
+        error: Box<ContractError>,
+    },
 }
@@ -2,10 +2,25 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
 use cw2::set_contract_version;
-use cw721_base::{ContractError, InstantiateMsg};
 
-use crate::msg::ExecuteMsg;
-use crate::state::{Cw721MetadataContract, CONTRACT_NAME, CONTRACT_VERSION};
+use crate::batch::{batch_mint, batch_send_nft, batch_transfer_nft, DEFAULT_MAX_BATCH_SIZE};
+use crate::collection::{update_collection_info, validate_collection_info};
+use crate::error::ContractError;
+use crate::metadata::{
+    freeze_collection_metadata, freeze_metadata, remove_token, update_metadata, update_token_uri,
+};
+use crate::msg::{ExecuteExtensionMsg, ExecuteMsg, InstantiateMsg};
+use crate::rarity::update_rarity;
+use crate::royalties::{validate_percentage, MAX_ROYALTY_PERCENTAGE};
+use crate::sale::{paid_mint, set_mint_phases, update_allowlist, withdraw_proceeds};
+use crate::soulbound::{ensure_transferable, revoke_token};
+use crate::state::{
+    Config, Cw721MetadataContract, Extension, COLLECTION_INFO, CONFIG, CONTRACT_NAME,
+    CONTRACT_VERSION,
+};
+use crate::supply::{ensure_supply, mint_next, record_burn, record_mint};
+use crate::traits::index_token;
+use crate::validation::validate_metadata;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -14,10 +29,30 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    let res = Cw721MetadataContract::default().instantiate(deps.branch(), env, info, msg)?;
+    let max_royalty_percentage = msg.max_royalty_percentage.unwrap_or(MAX_ROYALTY_PERCENTAGE);
+    validate_percentage(max_royalty_percentage, MAX_ROYALTY_PERCENTAGE)?;
+    let collection_info = msg.collection_info.unwrap_or_default();
+    validate_collection_info(deps.as_ref(), &collection_info, max_royalty_percentage)?;
+    let config = Config {
+        creator: info.sender.clone(),
+        max_royalty_percentage,
+        max_batch_size: msg.max_batch_size.unwrap_or(DEFAULT_MAX_BATCH_SIZE),
+        soulbound: msg.soulbound.unwrap_or_default(),
+        max_supply: msg.max_supply,
+        token_id_prefix: msg.token_id_prefix.unwrap_or_default(),
+        token_id_padding: msg.token_id_padding.unwrap_or_default(),
+    };
+    CONFIG.save(deps.storage, &config)?;
+    COLLECTION_INFO.save(deps.storage, &collection_info)?;
+
+    let base = cw721_base::InstantiateMsg {
+        name: msg.name,
+        symbol: msg.symbol,
+        minter: msg.minter,
+    };
+    let res = Cw721MetadataContract::default().instantiate(deps.branch(), env, info, base)?;
     // Explicitly set contract name and version, otherwise set to cw721-base info
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
-        .map_err(ContractError::Std)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(res)
 }
 
@@ -28,21 +63,105 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    Cw721MetadataContract::default().execute(deps, env, info, msg)
+    match msg.try_into() {
+        Ok(msg) => execute_base(deps, env, info, msg),
+        Err(msg) => execute_extension(deps, env, info, msg),
+    }
+}
+
+pub(crate) fn execute_base(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: cw721_base::ExecuteMsg<Extension>,
+) -> Result<Response, ContractError> {
+    ensure_transferable(deps.as_ref(), &msg)?;
+    let contract = Cw721MetadataContract::default();
+    let (minted, burned) = match &msg {
+        cw721_base::ExecuteMsg::Mint(mint) => {
+            ensure_supply(deps.storage, 1)?;
+            validate_metadata(deps.as_ref(), &mint.extension)?;
+            (Some((mint.token_id.clone(), mint.extension.clone())), None)
+        }
+        cw721_base::ExecuteMsg::Burn { token_id } => {
+            let token = contract.tokens.may_load(deps.storage, token_id)?;
+            (None, token.map(|token| (token_id.clone(), token.extension)))
+        }
+        _ => (None, None),
+    };
+    let res = contract.execute(deps.branch(), env, info, msg)?;
+    if let Some((token_id, extension)) = minted {
+        index_token(deps.storage, &token_id, &extension)?;
+        record_mint(deps.storage)?;
+    }
+    if let Some((token_id, extension)) = burned {
+        remove_token(deps.storage, &token_id, &extension)?;
+        record_burn(deps.storage)?;
+    }
+    Ok(res)
+}
+
+fn execute_extension(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteExtensionMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteExtensionMsg::UpdateMetadata { token_id, metadata } => {
+            update_metadata(deps, info, token_id, metadata)
+        }
+        ExecuteExtensionMsg::UpdateTokenUri {
+            token_id,
+            token_uri,
+        } => update_token_uri(deps, info, token_id, token_uri),
+        ExecuteExtensionMsg::FreezeMetadata { token_id } => freeze_metadata(deps, info, token_id),
+        ExecuteExtensionMsg::FreezeCollectionMetadata {} => freeze_collection_metadata(deps, info),
+        ExecuteExtensionMsg::UpdateCollectionInfo { collection_info } => {
+            update_collection_info(deps, info, collection_info)
+        }
+        ExecuteExtensionMsg::BatchMint { mints } => batch_mint(deps, env, info, mints),
+        ExecuteExtensionMsg::BatchTransferNft {
+            recipient,
+            token_ids,
+        } => batch_transfer_nft(deps, env, info, recipient, token_ids),
+        ExecuteExtensionMsg::BatchSendNft {
+            contract,
+            token_ids,
+            msg,
+        } => batch_send_nft(deps, env, info, contract, token_ids, msg),
+        ExecuteExtensionMsg::UpdateRarity { limit } => update_rarity(deps, info, limit),
+        ExecuteExtensionMsg::RevokeToken { token_id } => revoke_token(deps, info, token_id),
+        ExecuteExtensionMsg::MintNext {
+            owner,
+            token_uri,
+            extension,
+        } => mint_next(deps, env, info, owner, token_uri, extension),
+        ExecuteExtensionMsg::SetMintPhases { phases } => set_mint_phases(deps, info, phases),
+        ExecuteExtensionMsg::PaidMint { owner } => paid_mint(deps, env, info, owner),
+        ExecuteExtensionMsg::UpdateAllowlist { add, remove } => {
+            update_allowlist(deps, info, add, remove)
+        }
+        ExecuteExtensionMsg::WithdrawProceeds { recipient } => {
+            withdraw_proceeds(deps, info, recipient)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::state::{Extension, Metadata};
+    use crate::state::{CollectionInfo, Extension, Metadata, MintPhase, Trait};
+    use crate::validation::{MAX_ATTRIBUTES, MAX_IMAGE_DATA_LENGTH, MAX_STRING_LENGTH};
 
     use super::*;
 
     use cosmwasm_std::{
+        coin, coins, from_binary,
         testing::{mock_dependencies, mock_env, mock_info},
-        DepsMut, MessageInfo,
+        to_binary, BankMsg, Coin, CosmosMsg, Deps, DepsMut, MessageInfo, StdError, Timestamp,
     };
     use cw721::Cw721Query;
-    use cw721_base::{ExecuteMsg, MintMsg};
+    use cw721_base::{ExecuteMsg, InstantiateMsg, MintMsg};
 
     const CREATOR: &str = "creator";
 
@@ -61,7 +180,7 @@ mod tests {
         contract
     }
 
-    fn mint<'a>(
+    fn mint(
         deps: DepsMut,
         info: MessageInfo,
         token_id: String,
@@ -146,4 +265,907 @@ mod tests {
             .unwrap();
         assert_eq!(recipient.value, new_owner.to_string());
     }
+
+    fn instantiate_extension(deps: DepsMut) {
+        let msg = crate::msg::InstantiateMsg {
+            name: "Ark NFT Multichain".to_string(),
+            symbol: "Ark Protocol".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: Some(3),
+            soulbound: None,
+            max_supply: None,
+            token_id_prefix: None,
+            token_id_padding: None,
+        };
+        instantiate(deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    }
+
+    fn execute_extension(
+        deps: DepsMut,
+        sender: &str,
+        msg: ExecuteExtensionMsg,
+    ) -> Result<Response, ContractError> {
+        let msg = crate::msg::ExecuteMsg::Extension { msg };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    /// A phase of an hour from `start_time`, one mint per address for 100
+    /// uark.
+    fn mint_phase(name: &str, allowlist: bool, start_time: Timestamp) -> MintPhase {
+        MintPhase {
+            name: name.to_string(),
+            allowlist,
+            start_time,
+            end_time: start_time.plus_seconds(3600),
+            price: coin(100, "uark"),
+            per_address_limit: 1,
+            token_uri: None,
+            extension: None,
+        }
+    }
+
+    #[test]
+    fn update_and_freeze_metadata() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let contract = Cw721MetadataContract::default();
+        for token_id in ["0001", "0002"] {
+            let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Some(Metadata::default()),
+            });
+            execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        }
+
+        // Reveal
+        let revealed = Some(Metadata {
+            name: Some("Ark NFT #0001".to_string()),
+            ..Metadata::default()
+        });
+        let update = ExecuteExtensionMsg::UpdateMetadata {
+            token_id: "0001".to_string(),
+            metadata: revealed.clone(),
+        };
+        let err = execute_extension(deps.as_mut(), "owner", update.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        execute_extension(deps.as_mut(), CREATOR, update.clone()).unwrap();
+        let update_uri = ExecuteExtensionMsg::UpdateTokenUri {
+            token_id: "0001".to_string(),
+            token_uri: Some("ipfs://revealed".to_string()),
+        };
+        execute_extension(deps.as_mut(), CREATOR, update_uri.clone()).unwrap();
+        let nft_info = contract.nft_info(deps.as_ref(), "0001".into()).unwrap();
+        assert_eq!(nft_info.extension, revealed);
+        assert_eq!(nft_info.token_uri, Some("ipfs://revealed".to_string()));
+
+        // Frozen for good
+        let freeze = ExecuteExtensionMsg::FreezeMetadata {
+            token_id: "0001".to_string(),
+        };
+        execute_extension(deps.as_mut(), CREATOR, freeze).unwrap();
+        let err = execute_extension(deps.as_mut(), CREATOR, update).unwrap_err();
+        assert!(matches!(err, ContractError::MetadataFrozen { token_id } if token_id == "0001"));
+        let err = execute_extension(deps.as_mut(), CREATOR, update_uri).unwrap_err();
+        assert!(matches!(err, ContractError::MetadataFrozen { .. }));
+
+        let frozen = |deps: Deps, token_id: &str| -> bool {
+            let msg = crate::msg::QueryMsg::Extension {
+                msg: crate::msg::QueryExtensionMsg::MetadataFrozen {
+                    token_id: token_id.to_string(),
+                },
+            };
+            let res: crate::msg::MetadataFrozenResponse =
+                from_binary(&crate::query::query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.frozen
+        };
+        assert!(frozen(deps.as_ref(), "0001"));
+        assert!(!frozen(deps.as_ref(), "0002"));
+
+        // The collection freeze covers all tokens.
+        execute_extension(
+            deps.as_mut(),
+            CREATOR,
+            ExecuteExtensionMsg::FreezeCollectionMetadata {},
+        )
+        .unwrap();
+        assert!(frozen(deps.as_ref(), "0002"));
+        let update = ExecuteExtensionMsg::UpdateMetadata {
+            token_id: "0002".to_string(),
+            metadata: None,
+        };
+        let err = execute_extension(deps.as_mut(), CREATOR, update).unwrap_err();
+        assert!(matches!(err, ContractError::MetadataFrozen { .. }));
+    }
+
+    #[test]
+    fn update_collection_info() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let query_info = |deps: Deps| -> crate::msg::CollectionInfoResponse {
+            let msg = crate::msg::QueryMsg::Extension {
+                msg: crate::msg::QueryExtensionMsg::CollectionInfo {},
+            };
+            from_binary(&crate::query::query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        let res = query_info(deps.as_ref());
+        assert_eq!(res.creator, CREATOR);
+        assert_eq!(res.collection_info, CollectionInfo::default());
+
+        let collection_info = CollectionInfo {
+            description: Some("Ark NFT available on any IBC chain".to_string()),
+            image: Some("ipfs://image".to_string()),
+            banner_url: Some("ipfs://banner".to_string()),
+            external_link: Some("https://arkprotocol.io".to_string()),
+            explicit_content: false,
+            start_trading_time: Some(mock_env().block.time.plus_seconds(3600)),
+            royalty_percentage: Some(5),
+            royalty_payment_address: Some("artist".to_string()),
+        };
+        let update = ExecuteExtensionMsg::UpdateCollectionInfo {
+            collection_info: collection_info.clone(),
+        };
+        let err = execute_extension(deps.as_mut(), "owner", update.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        execute_extension(deps.as_mut(), CREATOR, update).unwrap();
+        assert_eq!(query_info(deps.as_ref()).collection_info, collection_info);
+
+        // Default royalties are bound by the maximum too.
+        let update = ExecuteExtensionMsg::UpdateCollectionInfo {
+            collection_info: CollectionInfo {
+                royalty_percentage: Some(101),
+                ..collection_info
+            },
+        };
+        let err = execute_extension(deps.as_mut(), CREATOR, update).unwrap_err();
+        assert!(matches!(err, ContractError::RoyaltyTooHigh { .. }));
+    }
+
+    #[test]
+    fn batch_mint_and_transfer() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let contract = Cw721MetadataContract::default();
+        let mint_msg = |token_id: &str| MintMsg {
+            token_id: token_id.to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: None,
+        };
+
+        // Nothing is minted if one token fails.
+        let mints = vec![mint_msg("0001"), mint_msg("0002"), mint_msg("0001")];
+        let msg = ExecuteExtensionMsg::BatchMint { mints };
+        let err = execute_extension(deps.as_mut(), CREATOR, msg).unwrap_err();
+        assert!(
+            matches!(&err, ContractError::BatchToken { token_id, error } if token_id == "0001"
+                && matches!(**error, ContractError::Base(cw721_base::ContractError::Claimed {})))
+        );
+        assert_eq!(contract.token_count(&deps.storage).unwrap(), 0);
+
+        let mints = vec![mint_msg("0001"), mint_msg("0002"), mint_msg("0003")];
+        let msg = ExecuteExtensionMsg::BatchMint {
+            mints: [mints.clone(), vec![mint_msg("0004")]].concat(),
+        };
+        let err = execute_extension(deps.as_mut(), CREATOR, msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::BatchTooLarge { size: 4, max: 3 }
+        ));
+        // Invalid metadata is reported for its token.
+        let invalid = MintMsg {
+            extension: Some(Metadata {
+                image: Some("http://image.png".to_string()),
+                ..Metadata::default()
+            }),
+            ..mint_msg("0002")
+        };
+        let msg = ExecuteExtensionMsg::BatchMint {
+            mints: vec![mint_msg("0001"), invalid],
+        };
+        let err = execute_extension(deps.as_mut(), CREATOR, msg).unwrap_err();
+        assert!(
+            matches!(&err, ContractError::BatchToken { token_id, error } if token_id == "0002"
+                && matches!(**error, ContractError::InvalidUriScheme { .. }))
+        );
+        assert_eq!(contract.token_count(&deps.storage).unwrap(), 0);
+        let msg = ExecuteExtensionMsg::BatchMint { mints };
+        let res = execute_extension(deps.as_mut(), CREATOR, msg).unwrap();
+        assert_eq!(res.events.len(), 3);
+        assert_eq!(contract.token_count(&deps.storage).unwrap(), 3);
+
+        // The other owner's token fails the whole transfer.
+        let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+            owner: "other".to_string(),
+            ..mint_msg("0004")
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let transfer = |token_ids: &[&str]| ExecuteExtensionMsg::BatchTransferNft {
+            recipient: "recipient".to_string(),
+            token_ids: token_ids.iter().map(|id| id.to_string()).collect(),
+        };
+        let err =
+            execute_extension(deps.as_mut(), "owner", transfer(&["0001", "0004"])).unwrap_err();
+        assert!(matches!(err, ContractError::BatchToken { token_id, .. } if token_id == "0004"));
+        let err =
+            execute_extension(deps.as_mut(), "owner", transfer(&["0001", "0001"])).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateToken { token_id } if token_id == "0001"));
+        let err =
+            execute_extension(deps.as_mut(), "owner", transfer(&["0001", "0009"])).unwrap_err();
+        assert!(
+            matches!(&err, ContractError::BatchToken { token_id, error } if token_id == "0009"
+                && matches!(**error, ContractError::Std(StdError::NotFound { .. })))
+        );
+        let err = execute_extension(
+            deps.as_mut(),
+            "owner",
+            transfer(&["0001", "0002", "0003", "0004"]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::BatchTooLarge { size: 4, max: 3 }
+        ));
+        execute_extension(deps.as_mut(), "owner", transfer(&["0001", "0002"])).unwrap();
+        let owner = contract
+            .owner_of(deps.as_ref(), mock_env(), "0002".to_string(), false)
+            .unwrap();
+        assert_eq!(owner.owner, "recipient");
+
+        // Each sent token notifies the receiving contract.
+        let msg = ExecuteExtensionMsg::BatchSendNft {
+            contract: "market".to_string(),
+            token_ids: vec!["0001".to_string(), "0002".to_string()],
+            msg: to_binary("list").unwrap(),
+        };
+        let res = execute_extension(deps.as_mut(), "recipient", msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        let msg = ExecuteExtensionMsg::BatchSendNft {
+            contract: "market".to_string(),
+            token_ids: vec!["0002".to_string(), "0002".to_string()],
+            msg: to_binary("list").unwrap(),
+        };
+        let err = execute_extension(deps.as_mut(), "market", msg).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateToken { token_id } if token_id == "0002"));
+    }
+
+    #[test]
+    fn metadata_validation() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let mut mint = |metadata: Metadata| {
+            let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+                token_id: "0001".to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Some(metadata),
+            });
+            execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg)
+        };
+        let attribute = |trait_type: &str| Trait {
+            display_type: None,
+            trait_type: trait_type.to_string(),
+            value: "value".to_string(),
+        };
+
+        let err = mint(Metadata {
+            image: Some("http://image.png".to_string()),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidUriScheme { field, .. } if field == "image"));
+        let err = mint(Metadata {
+            animation_url: Some("ftp://animation.mp4".to_string()),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::InvalidUriScheme { field, .. } if field == "animation_url")
+        );
+        let err = mint(Metadata {
+            external_url: Some("arkprotocol.io".to_string()),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::InvalidUriScheme { field, .. } if field == "external_url")
+        );
+        let err = mint(Metadata {
+            background_color: Some("#FFFFFF".to_string()),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBackgroundColor { .. }));
+        let err = mint(Metadata {
+            attributes: Some(vec![attribute("eyes"), attribute("eyes")]),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::DuplicateTraitType { trait_type } if trait_type == "eyes")
+        );
+        let err = mint(Metadata {
+            name: Some("a".repeat(MAX_STRING_LENGTH + 1)),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StringTooLong { field, .. } if field == "name"));
+        let err = mint(Metadata {
+            image: Some(format!("https://{}", "a".repeat(MAX_STRING_LENGTH))),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StringTooLong { field, .. } if field == "image"));
+        let err = mint(Metadata {
+            image_data: Some("a".repeat(MAX_IMAGE_DATA_LENGTH + 1)),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::StringTooLong { field, max }
+                if field == "image_data" && max == MAX_IMAGE_DATA_LENGTH
+        ));
+        let err = mint(Metadata {
+            attributes: Some(vec![Trait {
+                value: "a".repeat(MAX_STRING_LENGTH + 1),
+                ..attribute("eyes")
+            }]),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ContractError::StringTooLong { field, .. } if field == "value"));
+        let err = mint(Metadata {
+            attributes: Some(vec![Trait {
+                display_type: Some("a".repeat(MAX_STRING_LENGTH + 1)),
+                ..attribute("eyes")
+            }]),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::StringTooLong { field, .. } if field == "display_type")
+        );
+        let err = mint(Metadata {
+            attributes: Some(
+                (0..=MAX_ATTRIBUTES)
+                    .map(|i| attribute(&i.to_string()))
+                    .collect(),
+            ),
+            ..Metadata::default()
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::TooManyAttributes { count, max }
+                if count == MAX_ATTRIBUTES + 1 && max == MAX_ATTRIBUTES
+        ));
+
+        mint(Metadata {
+            image: Some("ipfs://image.png".to_string()),
+            image_data: Some("a".repeat(MAX_STRING_LENGTH + 1)),
+            animation_url: Some("ar://animation".to_string()),
+            external_url: Some("https://arkprotocol.io".to_string()),
+            background_color: Some("1a2B3c".to_string()),
+            attributes: Some(vec![attribute("eyes"), attribute("mouth")]),
+            ..Metadata::default()
+        })
+        .unwrap();
+
+        // Updates are checked the same way.
+        let update = ExecuteExtensionMsg::UpdateMetadata {
+            token_id: "0001".to_string(),
+            metadata: Some(Metadata {
+                external_url: Some("arkprotocol.io".to_string()),
+                ..Metadata::default()
+            }),
+        };
+        let err = execute_extension(deps.as_mut(), CREATOR, update).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidUriScheme { .. }));
+
+        // So is the collection info.
+        let update =
+            |collection_info| ExecuteExtensionMsg::UpdateCollectionInfo { collection_info };
+        let err = execute_extension(
+            deps.as_mut(),
+            CREATOR,
+            update(CollectionInfo {
+                banner_url: Some("http://banner.png".to_string()),
+                ..CollectionInfo::default()
+            }),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::InvalidUriScheme { field, .. } if field == "banner_url")
+        );
+        let err = execute_extension(
+            deps.as_mut(),
+            CREATOR,
+            update(CollectionInfo {
+                description: Some("a".repeat(MAX_STRING_LENGTH + 1)),
+                ..CollectionInfo::default()
+            }),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ContractError::StringTooLong { field, .. } if field == "description")
+        );
+    }
+
+    #[test]
+    fn soulbound() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        for (token_id, soulbound) in [("badge", Some(true)), ("0001", None)] {
+            let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Some(Metadata {
+                    soulbound,
+                    ..Metadata::default()
+                }),
+            });
+            execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        }
+        let owner = mock_info("owner", &[]);
+
+        let transfer = crate::msg::ExecuteMsg::TransferNft {
+            recipient: "other".to_string(),
+            token_id: "badge".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), transfer).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { token_id } if token_id == "badge"));
+        let send = crate::msg::ExecuteMsg::SendNft {
+            contract: "market".to_string(),
+            token_id: "badge".to_string(),
+            msg: to_binary("list").unwrap(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), send).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { .. }));
+        let approve = crate::msg::ExecuteMsg::Approve {
+            spender: "market".to_string(),
+            token_id: "badge".to_string(),
+            expires: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), approve).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { .. }));
+        let batch = ExecuteExtensionMsg::BatchTransferNft {
+            recipient: "other".to_string(),
+            token_ids: vec!["0001".to_string(), "badge".to_string()],
+        };
+        let err = execute_extension(deps.as_mut(), "owner", batch).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { .. }));
+
+        // Other tokens still move.
+        let transfer = crate::msg::ExecuteMsg::TransferNft {
+            recipient: "other".to_string(),
+            token_id: "0001".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner, transfer).unwrap();
+
+        // Metadata updates can not release or bind a token.
+        let update = |token_id: &str, soulbound| ExecuteExtensionMsg::UpdateMetadata {
+            token_id: token_id.to_string(),
+            metadata: Some(Metadata {
+                name: Some("Ark badge".to_string()),
+                soulbound,
+                ..Metadata::default()
+            }),
+        };
+        for (token_id, soulbound) in [
+            ("badge", None),
+            ("badge", Some(false)),
+            ("0001", Some(true)),
+        ] {
+            let err =
+                execute_extension(deps.as_mut(), CREATOR, update(token_id, soulbound)).unwrap_err();
+            assert!(
+                matches!(err, ContractError::SoulboundChanged { token_id: id } if id == token_id)
+            );
+        }
+        execute_extension(deps.as_mut(), CREATOR, update("badge", Some(true))).unwrap();
+        execute_extension(deps.as_mut(), CREATOR, update("0001", Some(false))).unwrap();
+
+        // Only the minter revokes, and only soulbound tokens.
+        let revoke = |token_id: &str| ExecuteExtensionMsg::RevokeToken {
+            token_id: token_id.to_string(),
+        };
+        let err = execute_extension(deps.as_mut(), "owner", revoke("badge")).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        let err = execute_extension(deps.as_mut(), CREATOR, revoke("0001")).unwrap_err();
+        assert!(matches!(err, ContractError::NotSoulbound { .. }));
+        execute_extension(deps.as_mut(), CREATOR, revoke("badge")).unwrap();
+        let contract = Cw721MetadataContract::default();
+        assert_eq!(contract.token_count(&deps.storage).unwrap(), 1);
+    }
+
+    #[test]
+    fn soulbound_collection() {
+        let mut deps = mock_dependencies();
+        let msg = crate::msg::InstantiateMsg {
+            name: "Ark Membership".to_string(),
+            symbol: "ARKM".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: None,
+            soulbound: Some(true),
+            max_supply: None,
+            token_id_prefix: None,
+            token_id_padding: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+            token_id: "0001".to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let owner = mock_info("owner", &[]);
+
+        let approve_all = crate::msg::ExecuteMsg::ApproveAll {
+            operator: "market".to_string(),
+            expires: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), approve_all).unwrap_err();
+        assert!(matches!(err, ContractError::SoulboundCollection {}));
+        let transfer = crate::msg::ExecuteMsg::TransferNft {
+            recipient: "other".to_string(),
+            token_id: "0001".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), transfer).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { .. }));
+
+        // The owner can still let go of the token.
+        let burn = crate::msg::ExecuteMsg::Burn {
+            token_id: "0001".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), owner, burn).unwrap();
+    }
+
+    #[test]
+    fn mint_next_and_supply() {
+        let mut deps = mock_dependencies();
+        let msg = crate::msg::InstantiateMsg {
+            name: "Ark NFT Multichain".to_string(),
+            symbol: "Ark Protocol".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: None,
+            soulbound: None,
+            max_supply: Some(3),
+            token_id_prefix: Some("ark-".to_string()),
+            token_id_padding: Some(4),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let mint_next = ExecuteExtensionMsg::MintNext {
+            owner: "owner".to_string(),
+            token_uri: Some("ipfs://token".to_string()),
+            extension: None,
+        };
+        let supply_info = |deps: Deps| -> crate::msg::SupplyInfoResponse {
+            let msg = crate::msg::QueryMsg::Extension {
+                msg: crate::msg::QueryExtensionMsg::SupplyInfo {},
+            };
+            from_binary(&crate::query::query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        let err = execute_extension(deps.as_mut(), "owner", mint_next.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        execute_extension(deps.as_mut(), CREATOR, mint_next.clone()).unwrap();
+        // Ids taken by an explicit mint are skipped.
+        let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+            token_id: "ark-0002".to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: None,
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let res = execute_extension(deps.as_mut(), CREATOR, mint_next.clone()).unwrap();
+        let token_id = res.attributes.iter().find(|a| a.key == "token_id").unwrap();
+        assert_eq!(token_id.value, "ark-0003");
+        let nft_info = Cw721MetadataContract::default()
+            .nft_info(deps.as_ref(), "ark-0003".to_string())
+            .unwrap();
+        assert_eq!(nft_info.token_uri, Some("ipfs://token".to_string()));
+
+        // Burned tokens do not free up supply.
+        let burn = crate::msg::ExecuteMsg::Burn {
+            token_id: "ark-0001".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), burn).unwrap();
+        let err = execute_extension(deps.as_mut(), CREATOR, mint_next).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MaxSupplyReached { max_supply: 3 }
+        ));
+        let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+            token_id: "ark-0001".to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: None,
+        });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::MaxSupplyReached { .. }));
+        let msg = ExecuteExtensionMsg::BatchMint {
+            mints: vec![MintMsg {
+                token_id: "ark-0004".to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: None,
+            }],
+        };
+        let err = execute_extension(deps.as_mut(), CREATOR, msg).unwrap_err();
+        assert!(matches!(err, ContractError::MaxSupplyReached { .. }));
+        assert_eq!(
+            supply_info(deps.as_ref()),
+            crate::msg::SupplyInfoResponse {
+                max_supply: Some(3),
+                minted: 3,
+                burned: 1,
+                remaining: Some(0),
+            }
+        );
+
+        // Without a maximum nothing remains to be counted.
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let mint_next = ExecuteExtensionMsg::MintNext {
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: None,
+        };
+        let res = execute_extension(deps.as_mut(), CREATOR, mint_next).unwrap();
+        let token_id = res.attributes.iter().find(|a| a.key == "token_id").unwrap();
+        assert_eq!(token_id.value, "1");
+        assert_eq!(
+            supply_info(deps.as_ref()),
+            crate::msg::SupplyInfoResponse {
+                max_supply: None,
+                minted: 1,
+                burned: 0,
+                remaining: None,
+            }
+        );
+    }
+
+    #[test]
+    fn paid_mint() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let contract = Cw721MetadataContract::default();
+        let start = mock_env().block.time;
+        let placeholder = Some(Metadata {
+            name: Some("Unrevealed".to_string()),
+            ..Metadata::default()
+        });
+        let phases = vec![
+            MintPhase {
+                price: coin(200, "uark"),
+                ..mint_phase("public", false, start.plus_seconds(3600))
+            },
+            MintPhase {
+                token_uri: Some("ipfs://unrevealed".to_string()),
+                extension: placeholder.clone(),
+                ..mint_phase("allowlist", true, start)
+            },
+        ];
+        let msg = ExecuteExtensionMsg::SetMintPhases {
+            phases: phases.clone(),
+        };
+        let err = execute_extension(deps.as_mut(), "buyer", msg.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        execute_extension(deps.as_mut(), CREATOR, msg).unwrap();
+        let allowlist = ExecuteExtensionMsg::UpdateAllowlist {
+            add: vec!["buyer".to_string()],
+            remove: vec![],
+        };
+        execute_extension(deps.as_mut(), CREATOR, allowlist).unwrap();
+
+        let mint = |deps: DepsMut, sender: &str, funds: &[Coin], time| {
+            let msg = crate::msg::ExecuteMsg::Extension {
+                msg: ExecuteExtensionMsg::PaidMint { owner: None },
+            };
+            let mut env = mock_env();
+            env.block.time = time;
+            execute(deps, env, mock_info(sender, funds), msg)
+        };
+
+        // Before the first phase
+        let err = mint(
+            deps.as_mut(),
+            "buyer",
+            &coins(100, "uark"),
+            start.minus_seconds(1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoMintPhase {}));
+
+        // Allowlist phase
+        let err = mint(deps.as_mut(), "other", &coins(100, "uark"), start).unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted { address } if address == "other"));
+        let err = mint(deps.as_mut(), "buyer", &coins(99, "uark"), start).unwrap_err();
+        assert!(
+            matches!(err, ContractError::InsufficientFunds { price } if price == coin(100, "uark"))
+        );
+        let err = mint(deps.as_mut(), "buyer", &coins(100, "uatom"), start).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFunds { .. }));
+        // Everything beyond the price is refunded, other denoms included.
+        let funds = [coin(150, "uark"), coin(10, "uatom")];
+        let res = mint(deps.as_mut(), "buyer", &funds, start).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "buyer".to_string(),
+                amount: vec![coin(50, "uark"), coin(10, "uatom")],
+            })
+        );
+        let nft_info = contract.nft_info(deps.as_ref(), "1".to_string()).unwrap();
+        assert_eq!(nft_info.token_uri, Some("ipfs://unrevealed".to_string()));
+        assert_eq!(nft_info.extension, placeholder);
+        let owner = contract
+            .owner_of(deps.as_ref(), mock_env(), "1".to_string(), false)
+            .unwrap();
+        assert_eq!(owner.owner, "buyer");
+        let err = mint(deps.as_mut(), "buyer", &coins(100, "uark"), start).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MintLimitReached { phase, limit: 1 } if phase == "allowlist"
+        ));
+
+        // Public phase, paid exactly.
+        let public = start.plus_seconds(3600);
+        let msg = crate::msg::ExecuteMsg::Extension {
+            msg: ExecuteExtensionMsg::PaidMint {
+                owner: Some("friend".to_string()),
+            },
+        };
+        let mut env = mock_env();
+        env.block.time = public;
+        let info = mock_info("other", &coins(200, "uark"));
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert!(res.messages.is_empty());
+        let token_id = res.attributes.iter().find(|a| a.key == "token_id").unwrap();
+        assert_eq!(token_id.value, "2");
+        let owner = contract
+            .owner_of(deps.as_ref(), mock_env(), "2".to_string(), false)
+            .unwrap();
+        assert_eq!(owner.owner, "friend");
+        let err = mint(
+            deps.as_mut(),
+            "other",
+            &coins(200, "uark"),
+            public.plus_seconds(3600),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoMintPhase {}));
+        // Only the minter mints with its own id and metadata, for free.
+        let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+            token_id: "0003".to_string(),
+            owner: CREATOR.to_string(),
+            token_uri: None,
+            extension: None,
+        });
+        let info = mock_info("buyer", &coins(200, "uark"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+
+        let msg = crate::msg::QueryMsg::Extension {
+            msg: crate::msg::QueryExtensionMsg::MintStatus {
+                phase: "allowlist".to_string(),
+                address: "buyer".to_string(),
+            },
+        };
+        let status: crate::msg::MintStatusResponse =
+            from_binary(&crate::query::query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(status.minted, 1);
+        assert!(status.allowlisted);
+
+        // Only the prices are withdrawn, once.
+        let withdraw = ExecuteExtensionMsg::WithdrawProceeds {
+            recipient: Some("treasury".to_string()),
+        };
+        let res = execute_extension(deps.as_mut(), CREATOR, withdraw.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(300, "uark"),
+            })
+        );
+        let res = execute_extension(deps.as_mut(), CREATOR, withdraw).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn mint_phase_validation() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut());
+        let start = mock_env().block.time;
+        let mut set_phases = |phases| {
+            let msg = ExecuteExtensionMsg::SetMintPhases { phases };
+            execute_extension(deps.as_mut(), CREATOR, msg)
+        };
+
+        let err = set_phases(vec![
+            mint_phase("a", false, start),
+            mint_phase("b", false, start.plus_seconds(1800)),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MintPhasesOverlap { name } if name == "b"));
+        let err = set_phases(vec![
+            mint_phase("a", false, start),
+            mint_phase("a", false, start.plus_seconds(3600)),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateMintPhase { name } if name == "a"));
+        let err = set_phases(vec![MintPhase {
+            end_time: start,
+            ..mint_phase("a", false, start)
+        }])
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMintPhase { .. }));
+        for price in [coin(0, "uark"), coin(100, ""), coin(100, "1ark")] {
+            let err = set_phases(vec![MintPhase {
+                price,
+                ..mint_phase("a", false, start)
+            }])
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidMintPrice { .. }));
+        }
+        let err = set_phases(vec![MintPhase {
+            per_address_limit: 0,
+            ..mint_phase("a", false, start)
+        }])
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMintLimit { .. }));
+        let err = set_phases(vec![MintPhase {
+            extension: Some(Metadata {
+                image: Some("http://image.png".to_string()),
+                ..Metadata::default()
+            }),
+            ..mint_phase("a", false, start)
+        }])
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidUriScheme { .. }));
+
+        // A phase may start when the one before ends.
+        set_phases(vec![
+            mint_phase("a", false, start),
+            mint_phase("b", false, start.plus_seconds(3600)),
+            MintPhase {
+                price: coin(
+                    100,
+                    "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2",
+                ),
+                ..mint_phase("c", false, start.plus_seconds(7200))
+            },
+        ])
+        .unwrap();
+    }
 }
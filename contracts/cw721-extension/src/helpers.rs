@@ -1 +1,22 @@
+use cosmwasm_std::{Addr, Storage};
 
+use crate::{
+    error::ContractError,
+    state::{Cw721MetadataContract, CONFIG},
+};
+
+pub fn ensure_minter(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let minter = Cw721MetadataContract::default().minter.load(storage)?;
+    if *sender != minter {
+        return Err(cw721_base::ContractError::Unauthorized {}.into());
+    }
+    Ok(())
+}
+
+pub fn ensure_creator(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let creator = CONFIG.load(storage)?.creator;
+    if *sender != creator {
+        return Err(cw721_base::ContractError::Unauthorized {}.into());
+    }
+    Ok(())
+}
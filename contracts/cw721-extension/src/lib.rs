@@ -1,8 +1,18 @@
+pub mod batch;
+pub mod collection;
 mod error;
 pub mod execute;
 pub mod helpers;
+pub mod metadata;
 pub mod msg;
 pub mod query;
+pub mod rarity;
+pub mod royalties;
+pub mod sale;
+pub mod soulbound;
 pub mod state;
+pub mod supply;
+pub mod traits;
+pub mod validation;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,127 @@
+use cosmwasm_std::{Deps, DepsMut, Empty, MessageInfo, Response, StdResult, Storage};
+
+use crate::{
+    error::ContractError,
+    helpers::ensure_minter,
+    msg::MetadataFrozenResponse,
+    rarity::remove_rarity,
+    soulbound::ensure_soulbound_unchanged,
+    state::{Cw721MetadataContract, Extension, COLLECTION_FROZEN, FROZEN_TOKENS},
+    traits::{index_token, unindex_token},
+    validation::validate_metadata,
+};
+
+fn collection_frozen(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(COLLECTION_FROZEN.may_load(storage)?.unwrap_or_default())
+}
+
+fn is_frozen(storage: &dyn Storage, token_id: &str) -> StdResult<bool> {
+    Ok(collection_frozen(storage)? || FROZEN_TOKENS.has(storage, token_id))
+}
+
+fn ensure_updatable(storage: &dyn Storage, token_id: &str) -> Result<(), ContractError> {
+    if is_frozen(storage, token_id)? {
+        return Err(ContractError::MetadataFrozen {
+            token_id: token_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub fn update_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    metadata: Extension,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    ensure_updatable(deps.storage, &token_id)?;
+    validate_metadata(deps.as_ref(), &metadata)?;
+
+    let tokens = Cw721MetadataContract::default().tokens;
+    let mut token = tokens.load(deps.storage, &token_id)?;
+    ensure_soulbound_unchanged(&token_id, &token.extension, &metadata)?;
+    unindex_token(deps.storage, &token_id, &token.extension)?;
+    index_token(deps.storage, &token_id, &metadata)?;
+    token.extension = metadata;
+    tokens.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_metadata")
+        .add_attribute("sender", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn update_token_uri(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    token_uri: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    ensure_updatable(deps.storage, &token_id)?;
+
+    let tokens = Cw721MetadataContract::default().tokens;
+    let mut token = tokens.load(deps.storage, &token_id)?;
+    token.token_uri = token_uri;
+    tokens.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_token_uri")
+        .add_attribute("sender", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn freeze_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    // Only existing tokens can be frozen.
+    Cw721MetadataContract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    FROZEN_TOKENS.save(deps.storage, &token_id, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "freeze_metadata")
+        .add_attribute("sender", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+pub fn freeze_collection_metadata(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    COLLECTION_FROZEN.save(deps.storage, &true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "freeze_collection_metadata")
+        .add_attribute("sender", info.sender))
+}
+
+/// Forgets the freeze, the rarity and the traits of a burned token, its id
+/// may be minted again.
+pub fn remove_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    extension: &Extension,
+) -> StdResult<()> {
+    FROZEN_TOKENS.remove(storage, token_id);
+    remove_rarity(storage, token_id)?;
+    unindex_token(storage, token_id, extension)
+}
+
+pub fn query_metadata_frozen(deps: Deps, token_id: String) -> StdResult<MetadataFrozenResponse> {
+    Ok(MetadataFrozenResponse {
+        frozen: is_frozen(deps.storage, &token_id)?,
+    })
+}
+
+pub fn query_collection_metadata_frozen(deps: Deps) -> StdResult<MetadataFrozenResponse> {
+    Ok(MetadataFrozenResponse {
+        frozen: collection_frozen(deps.storage)?,
+    })
+}
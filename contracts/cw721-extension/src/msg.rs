@@ -1,9 +1,458 @@
+use cosmwasm_std::{Binary, Decimal, Uint128};
+use cw721::Expiration;
+use cw721_base::MintMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::Extension;
+use crate::state::{CollectionInfo, Extension, MintPhase, Rarity};
 
-pub type ExecuteMsg = cw721_base::ExecuteMsg<Extension>;
+/// The messages of cw721-base, plus the ones of this contract in
+/// `Extension`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Transfer is a base message to move a token to another account without triggering actions
+    TransferNft { recipient: String, token_id: String },
+    /// Send is a base message to transfer a token to a contract and trigger an action
+    /// on the receiving contract.
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// Allows operator to transfer / send the token from the owner's account.
+    /// If expiration is set, then this allowance has a time/height limit
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove previously granted Approval
+    Revoke { spender: String, token_id: String },
+    /// Allows operator to transfer / send any token from the owner's account.
+    /// If expiration is set, then this allowance has a time/height limit
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove previously granted ApproveAll permission
+    RevokeAll { operator: String },
+    /// Mint a new NFT, can only be called by the contract minter
+    Mint(MintMsg<Extension>),
+    /// Burn an NFT the sender has access to
+    Burn { token_id: String },
+    /// Messages added by this contract.
+    Extension { msg: ExecuteExtensionMsg },
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteExtensionMsg {
+    /// Minter only. Replaces the metadata of a token whose metadata is not
+    /// frozen, its `soulbound` flag must stay the same.
+    UpdateMetadata {
+        token_id: String,
+        metadata: Extension,
+    },
+    /// Minter only. Replaces the token uri of a token whose metadata is not
+    /// frozen.
+    UpdateTokenUri {
+        token_id: String,
+        token_uri: Option<String>,
+    },
+    /// Minter only. Makes metadata and token uri of `token_id` immutable,
+    /// this can not be undone.
+    FreezeMetadata { token_id: String },
+    /// Minter only. Makes metadata and token uri of all tokens immutable,
+    /// this can not be undone.
+    FreezeCollectionMetadata {},
+    /// Creator only. Replaces the collection info.
+    UpdateCollectionInfo { collection_info: CollectionInfo },
+    /// Minter only. Mints all tokens or none of them.
+    BatchMint { mints: Vec<MintMsg<Extension>> },
+    /// Transfers all tokens to `recipient` or none of them.
+    BatchTransferNft {
+        recipient: String,
+        token_ids: Vec<String>,
+    },
+    /// Sends all tokens to `contract` or none of them, each with its own
+    /// `Cw721ReceiveMsg` carrying `msg`.
+    BatchSendNft {
+        contract: String,
+        token_ids: Vec<String>,
+        msg: Binary,
+    },
+    /// Creator only. Recomputes the rarity of all tokens, whose traits
+    /// changed since the last time. Each call handles up to `limit` tokens,
+    /// it is repeated until the `finished` attribute is `true`.
+    UpdateRarity { limit: Option<u32> },
+    /// Minter only. Burns a soulbound token, e.g. a badge that is no longer
+    /// valid.
+    RevokeToken { token_id: String },
+    /// Minter only. Mints a token with the next sequential id.
+    MintNext {
+        owner: String,
+        token_uri: Option<String>,
+        extension: Extension,
+    },
+    /// Creator only. Replaces the mint phases, which must have unique
+    /// names, a positive price and limit, and must not overlap.
+    SetMintPhases { phases: Vec<MintPhase> },
+    /// Anyone, for the price of the current mint phase. Mints a token with
+    /// the next sequential id and the token uri and metadata of the phase
+    /// to `owner`, or to the sender if not set.
+    PaidMint { owner: Option<String> },
+    /// Creator only. Adds and removes addresses of the allowlist.
+    UpdateAllowlist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Creator only. Sends the prices paid for mints to `recipient`, or to
+    /// the creator if not set.
+    WithdrawProceeds { recipient: Option<String> },
+}
+
+impl TryFrom<ExecuteMsg> for cw721_base::ExecuteMsg<Extension> {
+    type Error = ExecuteExtensionMsg;
+
+    /// The cw721-base message, or the extension message if it is none.
+    fn try_from(msg: ExecuteMsg) -> Result<Self, Self::Error> {
+        use cw721_base::ExecuteMsg as Base;
+        Ok(match msg {
+            ExecuteMsg::TransferNft {
+                recipient,
+                token_id,
+            } => Base::TransferNft {
+                recipient,
+                token_id,
+            },
+            ExecuteMsg::SendNft {
+                contract,
+                token_id,
+                msg,
+            } => Base::SendNft {
+                contract,
+                token_id,
+                msg,
+            },
+            ExecuteMsg::Approve {
+                spender,
+                token_id,
+                expires,
+            } => Base::Approve {
+                spender,
+                token_id,
+                expires,
+            },
+            ExecuteMsg::Revoke { spender, token_id } => Base::Revoke { spender, token_id },
+            ExecuteMsg::ApproveAll { operator, expires } => Base::ApproveAll { operator, expires },
+            ExecuteMsg::RevokeAll { operator } => Base::RevokeAll { operator },
+            ExecuteMsg::Mint(msg) => Base::Mint(msg),
+            ExecuteMsg::Burn { token_id } => Base::Burn { token_id },
+            ExecuteMsg::Extension { msg } => return Err(msg),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// Name of the NFT contract
+    pub name: String,
+    /// Symbol of the NFT contract
+    pub symbol: String,
+    /// The minter is the only one who can create new NFTs.
+    pub minter: String,
+    /// Description of the collection, including its default royalties.
+    pub collection_info: Option<CollectionInfo>,
+    /// Royalty percentages above this are rejected, 100 if not set.
+    pub max_royalty_percentage: Option<u64>,
+    /// Batches with more tokens are rejected, 50 if not set.
+    pub max_batch_size: Option<u32>,
+    /// Tokens can not be transferred or approved, only burned by their
+    /// owner or revoked by the minter.
+    pub soulbound: Option<bool>,
+    /// No more tokens can be minted once this many were, unlimited if not
+    /// set.
+    pub max_supply: Option<u64>,
+    /// Prefix of the ids assigned by `MintNext`, none if not set.
+    pub token_id_prefix: Option<String>,
+    /// Minimum number of digits of the ids assigned by `MintNext`, e.g. 4
+    /// for "0001". No padding if not set.
+    pub token_id_padding: Option<u32>,
+}
+
+/// The queries of cw721-base, plus the ones of this contract in
+/// `Extension`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Return the owner of the given token, error if token does not exist
+    /// Return type: OwnerOfResponse
+    OwnerOf {
+        token_id: String,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+    },
+    /// Return operator that can access all of the owner's tokens.
+    /// Return type: `ApprovalResponse`
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    /// Return approvals that a token has
+    /// Return type: `ApprovalsResponse`
+    Approvals {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// List all operators that can access all of the owner's tokens
+    /// Return type: `OperatorsResponse`
+    AllOperators {
+        owner: String,
+        /// unset or false will filter out expired items, you must set to true to see them
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Total number of tokens issued
+    NumTokens {},
+    /// Returns top-level metadata about the contract: `ContractInfoResponse`
+    ContractInfo {},
+    /// Returns metadata about one particular token: `NftInfoResponse`
+    NftInfo {
+        token_id: String,
+    },
+    /// Returns the result of both `NftInfo` and `OwnerOf` as one query:
+    /// `AllNftInfo`
+    AllNftInfo {
+        token_id: String,
+        /// unset or false will filter out expired approvals, you must set to true to see them
+        include_expired: Option<bool>,
+    },
+    /// Returns all tokens owned by the given address, [] if unset.
+    /// Return type: TokensResponse.
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists all token_ids controlled by the contract.
+    /// Return type: TokensResponse.
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Return the minter
+    Minter {},
+    /// Queries added by this contract.
+    Extension {
+        msg: QueryExtensionMsg,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryExtensionMsg {
+    /// CW-2981: the royalty owed when `token_id` is sold for `sale_price`,
+    /// in the denom of the sale. Return type: `RoyaltiesInfoResponse`
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// CW-2981: whether this contract implements royalties.
+    /// Return type: `CheckRoyaltiesResponse`
+    CheckRoyalties {},
+    /// Whether metadata and token uri of `token_id` can no longer change,
+    /// because the token or the collection is frozen.
+    /// Return type: `MetadataFrozenResponse`
+    MetadataFrozen { token_id: String },
+    /// Whether the metadata of all tokens is frozen.
+    /// Return type: `MetadataFrozenResponse`
+    CollectionMetadataFrozen {},
+    /// Return type: `CollectionInfoResponse`
+    CollectionInfo {},
+    /// Tokens with the attribute `trait_type` set to `value`.
+    /// Return type: `TokensResponse`
+    TokensByTrait {
+        trait_type: String,
+        value: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The values of `trait_type` and how many tokens have each.
+    /// Return type: `TraitValuesResponse`
+    TraitValues {
+        trait_type: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Score and rank of `token_id` as of the last `UpdateRarity`.
+    /// Return type: `TokenRarityResponse`
+    TokenRarity { token_id: String },
+    /// Tokens from the rarest on, `start_after` is a rank.
+    /// Return type: `RarityRankingResponse`
+    RarityRanking {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return type: `SupplyInfoResponse`
+    SupplyInfo {},
+    /// All mint phases and the current one.
+    /// Return type: `MintPhasesResponse`
+    MintPhases {},
+    /// Tokens `address` minted in phase `phase` and whether it is
+    /// allowlisted. Return type: `MintStatusResponse`
+    MintStatus { phase: String, address: String },
+}
+
+impl TryFrom<QueryMsg> for cw721_base::QueryMsg {
+    type Error = QueryExtensionMsg;
+
+    /// The cw721-base query, or the extension query if it is none.
+    fn try_from(msg: QueryMsg) -> Result<Self, Self::Error> {
+        use cw721_base::QueryMsg as Base;
+        Ok(match msg {
+            QueryMsg::OwnerOf {
+                token_id,
+                include_expired,
+            } => Base::OwnerOf {
+                token_id,
+                include_expired,
+            },
+            QueryMsg::Approval {
+                token_id,
+                spender,
+                include_expired,
+            } => Base::Approval {
+                token_id,
+                spender,
+                include_expired,
+            },
+            QueryMsg::Approvals {
+                token_id,
+                include_expired,
+            } => Base::Approvals {
+                token_id,
+                include_expired,
+            },
+            QueryMsg::AllOperators {
+                owner,
+                include_expired,
+                start_after,
+                limit,
+            } => Base::AllOperators {
+                owner,
+                include_expired,
+                start_after,
+                limit,
+            },
+            QueryMsg::NumTokens {} => Base::NumTokens {},
+            QueryMsg::ContractInfo {} => Base::ContractInfo {},
+            QueryMsg::NftInfo { token_id } => Base::NftInfo { token_id },
+            QueryMsg::AllNftInfo {
+                token_id,
+                include_expired,
+            } => Base::AllNftInfo {
+                token_id,
+                include_expired,
+            },
+            QueryMsg::Tokens {
+                owner,
+                start_after,
+                limit,
+            } => Base::Tokens {
+                owner,
+                start_after,
+                limit,
+            },
+            QueryMsg::AllTokens { start_after, limit } => Base::AllTokens { start_after, limit },
+            QueryMsg::Minter {} => Base::Minter {},
+            QueryMsg::Extension { msg } => return Err(msg),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    /// Receiver of the royalty, empty if there is none.
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MetadataFrozenResponse {
+    pub frozen: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionInfoResponse {
+    pub creator: String,
+    pub collection_info: CollectionInfo,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TraitValueCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TraitValuesResponse {
+    pub trait_type: String,
+    pub values: Vec<TraitValueCount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenRarityResponse {
+    pub token_id: String,
+    /// None if the token was minted after the last `UpdateRarity`.
+    pub rarity: Option<Rarity>,
+    /// Whether traits changed since the last `UpdateRarity`.
+    pub stale: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RankedToken {
+    pub token_id: String,
+    pub score: Decimal,
+    pub rank: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RarityRankingResponse {
+    pub tokens: Vec<RankedToken>,
+    /// Whether traits changed since the last `UpdateRarity`.
+    pub stale: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupplyInfoResponse {
+    pub max_supply: Option<u64>,
+    pub minted: u64,
+    pub burned: u64,
+    /// Tokens that can still be minted, None without a maximum supply.
+    pub remaining: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintPhasesResponse {
+    pub phases: Vec<MintPhase>,
+    /// The phase open at the current block time.
+    pub current: Option<MintPhase>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintStatusResponse {
+    pub minted: u32,
+    pub allowlisted: bool,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
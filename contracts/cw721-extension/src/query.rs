@@ -1,14 +1,452 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, Env, StdResult};
-use cw721_base::QueryMsg;
+use cosmwasm_std::{to_binary, Binary, Deps, Env, StdResult};
 
+use crate::collection::query_collection_info;
+use crate::metadata::{query_collection_metadata_frozen, query_metadata_frozen};
+use crate::msg::{QueryExtensionMsg, QueryMsg};
+use crate::rarity::{query_rarity_ranking, query_token_rarity};
+use crate::royalties::{check_royalties, query_royalty_info};
+use crate::sale::{query_mint_phases, query_mint_status};
 use crate::state::Cw721MetadataContract;
+use crate::supply::query_supply_info;
+use crate::traits::{query_tokens_by_trait, query_trait_values};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    Cw721MetadataContract::default().query(deps, env, msg)
+    match msg.try_into() {
+        Ok(msg) => Cw721MetadataContract::default().query(deps, env, msg),
+        Err(msg) => query_extension(deps, env, msg),
+    }
+}
+
+fn query_extension(deps: Deps, env: Env, msg: QueryExtensionMsg) -> StdResult<Binary> {
+    match msg {
+        QueryExtensionMsg::RoyaltyInfo {
+            token_id,
+            sale_price,
+        } => to_binary(&query_royalty_info(deps, token_id, sale_price)?),
+        QueryExtensionMsg::CheckRoyalties {} => to_binary(&check_royalties()),
+        QueryExtensionMsg::MetadataFrozen { token_id } => {
+            to_binary(&query_metadata_frozen(deps, token_id)?)
+        }
+        QueryExtensionMsg::CollectionMetadataFrozen {} => {
+            to_binary(&query_collection_metadata_frozen(deps)?)
+        }
+        QueryExtensionMsg::CollectionInfo {} => to_binary(&query_collection_info(deps)?),
+        QueryExtensionMsg::TokensByTrait {
+            trait_type,
+            value,
+            start_after,
+            limit,
+        } => to_binary(&query_tokens_by_trait(
+            deps,
+            trait_type,
+            value,
+            start_after,
+            limit,
+        )?),
+        QueryExtensionMsg::TraitValues {
+            trait_type,
+            start_after,
+            limit,
+        } => to_binary(&query_trait_values(deps, trait_type, start_after, limit)?),
+        QueryExtensionMsg::TokenRarity { token_id } => {
+            to_binary(&query_token_rarity(deps, token_id)?)
+        }
+        QueryExtensionMsg::RarityRanking { start_after, limit } => {
+            to_binary(&query_rarity_ranking(deps, start_after, limit)?)
+        }
+        QueryExtensionMsg::SupplyInfo {} => to_binary(&query_supply_info(deps)?),
+        QueryExtensionMsg::MintPhases {} => to_binary(&query_mint_phases(deps, env)?),
+        QueryExtensionMsg::MintStatus { phase, address } => {
+            to_binary(&query_mint_status(deps, phase, address)?)
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::{
+        from_binary,
+        testing::{mock_dependencies, mock_env, mock_info},
+        Decimal, DepsMut, Response, Uint128,
+    };
+    use cw721::TokensResponse;
+    use cw721_base::MintMsg;
+
+    use crate::execute::{execute, instantiate};
+    use crate::msg::{
+        CheckRoyaltiesResponse, ExecuteExtensionMsg, ExecuteMsg, InstantiateMsg, RankedToken,
+        RarityRankingResponse, RoyaltiesInfoResponse, TokenRarityResponse, TraitValuesResponse,
+    };
+    use crate::state::{CollectionInfo, Metadata, Rarity, Trait};
+    use crate::ContractError;
+
+    const CREATOR: &str = "creator";
+
+    fn instantiate_extension(
+        deps: DepsMut,
+        collection_info: Option<CollectionInfo>,
+        max_royalty_percentage: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let msg = InstantiateMsg {
+            name: "Ark NFT Multichain".to_string(),
+            symbol: "Ark Protocol".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info,
+            max_royalty_percentage,
+            max_batch_size: None,
+            soulbound: None,
+            max_supply: None,
+            token_id_prefix: None,
+            token_id_padding: None,
+        };
+        instantiate(deps, mock_env(), mock_info(CREATOR, &[]), msg)
+    }
+
+    fn mint(
+        deps: DepsMut,
+        token_id: &str,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<&str>,
+    ) -> Result<(), ContractError> {
+        let msg = ExecuteMsg::Mint(MintMsg {
+            token_id: token_id.to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: Some(Metadata {
+                royalty_percentage,
+                royalty_payment_address: royalty_payment_address.map(String::from),
+                ..Metadata::default()
+            }),
+        });
+        execute(deps, mock_env(), mock_info(CREATOR, &[]), msg)?;
+        Ok(())
+    }
+
+    fn royalty_info(deps: Deps, token_id: &str, sale_price: u128) -> RoyaltiesInfoResponse {
+        let msg = QueryMsg::Extension {
+            msg: QueryExtensionMsg::RoyaltyInfo {
+                token_id: token_id.to_string(),
+                sale_price: Uint128::new(sale_price),
+            },
+        };
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn royalties() {
+        let mut deps = mock_dependencies();
+        let collection_info = CollectionInfo {
+            royalty_percentage: Some(5),
+            royalty_payment_address: Some("artist".to_string()),
+            ..CollectionInfo::default()
+        };
+        instantiate_extension(deps.as_mut(), Some(collection_info), Some(20)).unwrap();
+
+        // Token royalties fall back to the collection defaults.
+        mint(deps.as_mut(), "0001", None, None).unwrap();
+        mint(deps.as_mut(), "0002", Some(10), Some("gallery")).unwrap();
+        mint(deps.as_mut(), "0003", Some(10), None).unwrap();
+        assert_eq!(
+            royalty_info(deps.as_ref(), "0001", 1000),
+            RoyaltiesInfoResponse {
+                address: "artist".to_string(),
+                royalty_amount: Uint128::new(50),
+            }
+        );
+        assert_eq!(
+            royalty_info(deps.as_ref(), "0002", 1000),
+            RoyaltiesInfoResponse {
+                address: "gallery".to_string(),
+                royalty_amount: Uint128::new(100),
+            }
+        );
+        assert_eq!(
+            royalty_info(deps.as_ref(), "0003", 1000).address,
+            "artist".to_string()
+        );
+
+        let err = mint(deps.as_mut(), "0004", Some(21), None).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::RoyaltyTooHigh {
+                percentage: 21,
+                max: 20
+            }
+        ));
+
+        let msg = QueryMsg::Extension {
+            msg: QueryExtensionMsg::CheckRoyalties {},
+        };
+        let res: CheckRoyaltiesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(res.royalty_payments);
+
+        // Base queries still work.
+        let res: cw721::NumTokensResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NumTokens {}).unwrap())
+                .unwrap();
+        assert_eq!(res.count, 3);
+    }
+
+    #[test]
+    fn no_royalties() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut(), None, None).unwrap();
+        mint(deps.as_mut(), "0001", Some(10), None).unwrap();
+        assert_eq!(
+            royalty_info(deps.as_ref(), "0001", 1000),
+            RoyaltiesInfoResponse {
+                address: String::new(),
+                royalty_amount: Uint128::zero(),
+            }
+        );
+
+        // The maximum can not exceed 100 percent.
+        let err = instantiate_extension(deps.as_mut(), None, Some(101)).unwrap_err();
+        assert!(matches!(err, ContractError::RoyaltyTooHigh { .. }));
+    }
+
+    #[test]
+    fn trait_index() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut(), None, None).unwrap();
+        let metadata = |background: &str| {
+            Some(Metadata {
+                attributes: Some(vec![Trait {
+                    display_type: None,
+                    trait_type: "Background".to_string(),
+                    value: background.to_string(),
+                }]),
+                ..Metadata::default()
+            })
+        };
+        for (token_id, background) in [("0001", "Gold"), ("0002", "Blue"), ("0003", "Gold")] {
+            let msg = ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: metadata(background),
+            });
+            execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        }
+        let tokens_by_trait = |deps: Deps, value: &str, start_after: Option<&str>| {
+            let msg = QueryMsg::Extension {
+                msg: QueryExtensionMsg::TokensByTrait {
+                    trait_type: "Background".to_string(),
+                    value: value.to_string(),
+                    start_after: start_after.map(String::from),
+                    limit: Some(1),
+                },
+            };
+            let res: TokensResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.tokens
+        };
+        let trait_values = |deps: Deps| {
+            let msg = QueryMsg::Extension {
+                msg: QueryExtensionMsg::TraitValues {
+                    trait_type: "Background".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            };
+            let res: TraitValuesResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.values
+                .into_iter()
+                .map(|value| (value.value, value.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tokens_by_trait(deps.as_ref(), "Gold", None), vec!["0001"]);
+        assert_eq!(
+            tokens_by_trait(deps.as_ref(), "Gold", Some("0001")),
+            vec!["0003"]
+        );
+        assert_eq!(
+            trait_values(deps.as_ref()),
+            vec![("Blue".to_string(), 1), ("Gold".to_string(), 2)]
+        );
+
+        // Updates move the token, burns remove it.
+        let msg = ExecuteMsg::Extension {
+            msg: ExecuteExtensionMsg::UpdateMetadata {
+                token_id: "0002".to_string(),
+                metadata: metadata("Gold"),
+            },
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        let msg = ExecuteMsg::Burn {
+            token_id: "0001".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(trait_values(deps.as_ref()), vec![("Gold".to_string(), 2)]);
+        assert_eq!(tokens_by_trait(deps.as_ref(), "Gold", None), vec!["0002"]);
+        assert!(tokens_by_trait(deps.as_ref(), "Blue", None).is_empty());
+    }
+
+    #[test]
+    fn rarity() {
+        let mut deps = mock_dependencies();
+        instantiate_extension(deps.as_mut(), None, None).unwrap();
+        let attribute = |trait_type: &str, value: &str| Trait {
+            display_type: None,
+            trait_type: trait_type.to_string(),
+            value: value.to_string(),
+        };
+        let tokens = [
+            ("0001", vec![attribute("Background", "Gold")]),
+            ("0002", vec![attribute("Background", "Blue")]),
+            (
+                "0003",
+                vec![attribute("Background", "Blue"), attribute("Hat", "Crown")],
+            ),
+            ("0004", vec![attribute("Background", "Blue")]),
+        ];
+        for (token_id, attributes) in tokens {
+            let msg = ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Some(Metadata {
+                    attributes: Some(attributes),
+                    ..Metadata::default()
+                }),
+            });
+            execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        }
+        let token_rarity = |deps: Deps, token_id: &str| {
+            let msg = QueryMsg::Extension {
+                msg: QueryExtensionMsg::TokenRarity {
+                    token_id: token_id.to_string(),
+                },
+            };
+            let res: TokenRarityResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res
+        };
+        let res = token_rarity(deps.as_ref(), "0001");
+        assert_eq!(res.rarity, None);
+        assert!(res.stale);
+
+        let update = ExecuteMsg::Extension {
+            msg: ExecuteExtensionMsg::UpdateRarity { limit: None },
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update).unwrap();
+
+        // 4/1 + 4/3 for the crown and a blue background, 4/1 for gold.
+        let res = token_rarity(deps.as_ref(), "0003");
+        assert!(!res.stale);
+        assert_eq!(
+            res.rarity,
+            Some(Rarity {
+                score: Decimal::from_ratio(16u64, 3u64),
+                rank: 1,
+            })
+        );
+        assert_eq!(token_rarity(deps.as_ref(), "0001").rarity.unwrap().rank, 2);
+
+        let msg = QueryMsg::Extension {
+            msg: QueryExtensionMsg::RarityRanking {
+                start_after: Some(2),
+                limit: None,
+            },
+        };
+        let res: RarityRankingResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res.tokens,
+            vec![
+                RankedToken {
+                    token_id: "0002".to_string(),
+                    score: Decimal::from_ratio(4u64, 3u64),
+                    rank: 3,
+                },
+                RankedToken {
+                    token_id: "0004".to_string(),
+                    score: Decimal::from_ratio(4u64, 3u64),
+                    rank: 4,
+                },
+            ]
+        );
+
+        // A burn changes the frequencies.
+        let msg = ExecuteMsg::Burn {
+            token_id: "0004".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert!(token_rarity(deps.as_ref(), "0001").stale);
+        // The burned token loses its rarity and its rank at once.
+        assert_eq!(token_rarity(deps.as_ref(), "0004").rarity, None);
+        let msg = QueryMsg::Extension {
+            msg: QueryExtensionMsg::RarityRanking {
+                start_after: Some(2),
+                limit: None,
+            },
+        };
+        let res: RarityRankingResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.tokens.len(), 1);
+
+        // Recomputed in passes of two tokens: scoring and ranking.
+        let update = ExecuteMsg::Extension {
+            msg: ExecuteExtensionMsg::UpdateRarity { limit: Some(2) },
+        };
+        let mut passes = 0;
+        loop {
+            passes += 1;
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(CREATOR, &[]),
+                update.clone(),
+            )
+            .unwrap();
+            let finished = res.attributes.iter().find(|a| a.key == "finished").unwrap();
+            if finished.value == "true" {
+                break;
+            }
+            assert!(token_rarity(deps.as_ref(), "0001").stale);
+        }
+        assert_eq!(passes, 4);
+        // 3/1 for gold, 3/2 for a blue background.
+        let res = token_rarity(deps.as_ref(), "0001");
+        assert!(!res.stale);
+        assert_eq!(
+            res.rarity,
+            Some(Rarity {
+                score: Decimal::from_ratio(3u64, 1u64),
+                rank: 2,
+            })
+        );
+        assert_eq!(token_rarity(deps.as_ref(), "0004").rarity, None);
+        let msg = QueryMsg::Extension {
+            msg: QueryExtensionMsg::RarityRanking {
+                start_after: None,
+                limit: None,
+            },
+        };
+        let res: RarityRankingResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ranking: Vec<_> = res
+            .tokens
+            .iter()
+            .map(|token| (token.token_id.as_str(), token.rank))
+            .collect();
+        assert_eq!(ranking, vec![("0003", 1), ("0001", 2), ("0002", 3)]);
+    }
+}
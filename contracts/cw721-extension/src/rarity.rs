@@ -0,0 +1,358 @@
+use cosmwasm_std::{Decimal, Deps, DepsMut, MessageInfo, Order, Response, StdResult, Storage};
+use cw721::Cw721Query;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    helpers::ensure_creator,
+    msg::{RankedToken, RarityRankingResponse, TokenRarityResponse},
+    state::{
+        Cw721MetadataContract, Extension, Rarity, RarityPhase, RarityUpdate, RARITY_RANKING,
+        RARITY_SCORES, RARITY_STALE, RARITY_UPDATE, TOKEN_RARITY, TRAIT_COUNTS,
+    },
+};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+/// Tokens handled per `UpdateRarity` if no limit is given.
+const DEFAULT_UPDATE_LIMIT: u32 = 30;
+/// The most tokens `all_tokens` of cw721-base lists at once.
+const MAX_UPDATE_LIMIT: u32 = 100;
+
+/// Marks the rarity as outdated, called whenever the trait index changes.
+pub fn mark_stale(storage: &mut dyn Storage) -> StdResult<()> {
+    if let Some(mut update) = RARITY_UPDATE.may_load(storage)? {
+        update.changed = true;
+        RARITY_UPDATE.save(storage, &update)?;
+    }
+    RARITY_STALE.save(storage, &true)
+}
+
+fn is_stale(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(RARITY_STALE.may_load(storage)?.unwrap_or_default())
+}
+
+/// The sum of `tokens / tokens with the trait` over the traits of a token.
+fn score(storage: &dyn Storage, total: u64, extension: &Extension) -> StdResult<Decimal> {
+    let mut score = Decimal::zero();
+    for attribute in extension
+        .iter()
+        .flat_map(|metadata| metadata.attributes.iter().flatten())
+    {
+        let key = (attribute.trait_type.as_str(), attribute.value.as_str());
+        if let Some(count) = TRAIT_COUNTS.may_load(storage, key)? {
+            score += Decimal::from_ratio(total, count);
+        }
+    }
+    Ok(score)
+}
+
+/// Orders higher scores first.
+fn score_key(score: Decimal) -> Vec<u8> {
+    (u128::MAX - score.atomics().u128()).to_be_bytes().to_vec()
+}
+
+/// Moves `token_id` to `rank`, or empties the rank if `None`.
+fn save_rank(
+    storage: &mut dyn Storage,
+    rank: u64,
+    token_id: Option<&str>,
+    score: Decimal,
+) -> StdResult<()> {
+    match token_id {
+        Some(token_id) => {
+            TOKEN_RARITY.save(storage, token_id, &Rarity { score, rank })?;
+            RARITY_RANKING.save(storage, rank, &token_id.to_string())
+        }
+        None => {
+            RARITY_RANKING.remove(storage, rank);
+            Ok(())
+        }
+    }
+}
+
+/// Drops the rarity of a burned token and frees its rank, unless a
+/// recomputation in progress gave the rank to another token already.
+pub fn remove_rarity(storage: &mut dyn Storage, token_id: &str) -> StdResult<()> {
+    if let Some(Rarity { rank, .. }) = TOKEN_RARITY.may_load(storage, token_id)? {
+        TOKEN_RARITY.remove(storage, token_id);
+        if RARITY_RANKING.may_load(storage, rank)?.as_deref() == Some(token_id) {
+            RARITY_RANKING.remove(storage, rank);
+        }
+    }
+    Ok(())
+}
+
+/// Runs up to `limit` steps of the recomputation, returns the next phase or
+/// `None` once it is done.
+fn step(
+    deps: DepsMut,
+    total: u64,
+    phase: RarityPhase,
+    limit: usize,
+) -> StdResult<(usize, Option<RarityPhase>)> {
+    let contract = Cw721MetadataContract::default();
+    match phase {
+        RarityPhase::Scoring { start_after } => {
+            let limit = limit.min(MAX_UPDATE_LIMIT as usize);
+            let tokens = contract
+                .all_tokens(deps.as_ref(), start_after, Some(limit as u32))?
+                .tokens;
+            for token_id in &tokens {
+                let token = contract.tokens.load(deps.storage, token_id)?;
+                let score = score(deps.storage, total, &token.extension)?;
+                RARITY_SCORES.save(deps.storage, (score_key(score), token_id), &score)?;
+            }
+            let next = match tokens.last() {
+                Some(token_id) if tokens.len() == limit => RarityPhase::Scoring {
+                    start_after: Some(token_id.clone()),
+                },
+                _ => RarityPhase::Ranking { rank: 1 },
+            };
+            Ok((tokens.len(), Some(next)))
+        }
+        // Ranked scores are removed, so the next ones are always first.
+        RarityPhase::Ranking { mut rank } => {
+            let scores = RARITY_SCORES
+                .range(deps.storage, None, None, Order::Ascending)
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?;
+            for ((key, token_id), score) in &scores {
+                RARITY_SCORES.remove(deps.storage, (key.clone(), token_id));
+                // Tokens burned since they were scored are left out.
+                if contract.tokens.has(deps.storage, token_id) {
+                    save_rank(deps.storage, rank, Some(token_id), *score)?;
+                    rank += 1;
+                }
+            }
+            let next = if scores.len() == limit {
+                RarityPhase::Ranking { rank }
+            } else {
+                RarityPhase::Cleanup { rank }
+            };
+            Ok((scores.len(), Some(next)))
+        }
+        RarityPhase::Cleanup { rank } => {
+            let ranks = RARITY_RANKING
+                .keys(
+                    deps.storage,
+                    Some(Bound::inclusive(rank)),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?;
+            for rank in &ranks {
+                save_rank(deps.storage, *rank, None, Decimal::zero())?;
+            }
+            let next = match ranks.last() {
+                Some(last) if ranks.len() == limit => Some(RarityPhase::Cleanup { rank: last + 1 }),
+                _ => None,
+            };
+            Ok((ranks.len(), next))
+        }
+    }
+}
+
+/// Recomputes the score and rank of every token, in passes of up to
+/// `limit` tokens. The score of a token is the sum of `tokens / tokens with
+/// the trait` over its traits. Equal scores are ranked by token id.
+pub fn update_rarity(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+
+    let limit = limit.unwrap_or(DEFAULT_UPDATE_LIMIT).min(MAX_UPDATE_LIMIT) as usize;
+    let mut update = match RARITY_UPDATE.may_load(deps.storage)? {
+        Some(update) => update,
+        None => RarityUpdate {
+            total: Cw721MetadataContract::default().token_count(deps.storage)?,
+            changed: false,
+            phase: RarityPhase::Scoring { start_after: None },
+        },
+    };
+    let mut budget = limit;
+    let mut finished = false;
+    while budget > 0 {
+        let (done, next) = step(deps.branch(), update.total, update.phase.clone(), budget)?;
+        budget = budget.saturating_sub(done.max(1));
+        match next {
+            Some(phase) => update.phase = phase,
+            None => {
+                finished = true;
+                break;
+            }
+        }
+    }
+    if finished {
+        RARITY_UPDATE.remove(deps.storage);
+        // Traits that changed meanwhile are not reflected in every score.
+        RARITY_STALE.save(deps.storage, &update.changed)?;
+    } else {
+        RARITY_UPDATE.save(deps.storage, &update)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_rarity")
+        .add_attribute("sender", info.sender)
+        .add_attribute("tokens", update.total.to_string())
+        .add_attribute("finished", finished.to_string()))
+}
+
+pub fn query_token_rarity(deps: Deps, token_id: String) -> StdResult<TokenRarityResponse> {
+    let rarity = TOKEN_RARITY.may_load(deps.storage, &token_id)?;
+    Ok(TokenRarityResponse {
+        token_id,
+        rarity,
+        stale: is_stale(deps.storage)?,
+    })
+}
+
+pub fn query_rarity_ranking(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RarityRankingResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    // While a recomputation is in progress a token may still hold a rank it
+    // has since left, or have been burned, only its current rank is listed.
+    let tokens = RARITY_RANKING
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|item| {
+            let (rank, token_id) = match item {
+                Ok(item) => item,
+                Err(err) => return Some(Err(err)),
+            };
+            match TOKEN_RARITY.may_load(deps.storage, &token_id) {
+                Ok(Some(rarity)) if rarity.rank == rank => Some(Ok(RankedToken {
+                    token_id,
+                    score: rarity.score,
+                    rank,
+                })),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            }
+        })
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(RarityRankingResponse {
+        tokens,
+        stale: is_stale(deps.storage)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw721_base::MintMsg;
+
+    use crate::execute::{execute, instantiate};
+    use crate::msg::{ExecuteExtensionMsg, ExecuteMsg, InstantiateMsg};
+    use crate::state::{Metadata, Trait};
+
+    const CREATOR: &str = "creator";
+
+    /// Mints tokens "1" to "5", the higher the id the more common its
+    /// background, and ranks them.
+    fn setup(mut deps: DepsMut) {
+        let msg = InstantiateMsg {
+            name: "Ark NFT Multichain".to_string(),
+            symbol: "Ark Protocol".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: None,
+            soulbound: None,
+            max_supply: None,
+            token_id_prefix: None,
+            token_id_padding: None,
+        };
+        instantiate(deps.branch(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        for (token_id, background) in [
+            ("1", "Gold"),
+            ("2", "Silver"),
+            ("3", "Silver"),
+            ("4", "Blue"),
+            ("5", "Blue"),
+        ] {
+            let msg = ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Some(Metadata {
+                    attributes: Some(vec![Trait {
+                        display_type: None,
+                        trait_type: "Background".to_string(),
+                        value: background.to_string(),
+                    }]),
+                    ..Metadata::default()
+                }),
+            });
+            execute(deps.branch(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        }
+        update_rarity(deps, mock_info(CREATOR, &[]), None).unwrap();
+    }
+
+    fn ranking(storage: &dyn Storage) -> Vec<(u64, String)> {
+        RARITY_RANKING
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn cleanup_removes_ranks_left_over() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+
+        // Ranks past the end are removed a few at a time.
+        let phase = RarityPhase::Cleanup { rank: 2 };
+        let (done, next) = step(deps.as_mut(), 5, phase, 3).unwrap();
+        assert_eq!(done, 3);
+        assert_eq!(next, Some(RarityPhase::Cleanup { rank: 5 }));
+        let (done, next) = step(deps.as_mut(), 5, next.unwrap(), 3).unwrap();
+        assert_eq!(done, 1);
+        assert_eq!(next, None);
+        assert_eq!(ranking(&deps.storage), vec![(1, "1".to_string())]);
+        // Tokens keep their rarity until they are ranked again.
+        assert_eq!(TOKEN_RARITY.load(&deps.storage, "5").unwrap().rank, 5);
+        let (done, next) = step(deps.as_mut(), 5, RarityPhase::Cleanup { rank: 2 }, 3).unwrap();
+        assert_eq!((done, next), (0, None));
+    }
+
+    #[test]
+    fn recompute_after_burn() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut());
+        let burn = ExecuteMsg::Burn {
+            token_id: "2".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), burn).unwrap();
+        assert_eq!(TOKEN_RARITY.may_load(&deps.storage, "2").unwrap(), None);
+
+        // The last rank is left over once the four tokens are ranked, and
+        // is cleaned up in the same pass.
+        let update = ExecuteMsg::Extension {
+            msg: ExecuteExtensionMsg::UpdateRarity { limit: None },
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update).unwrap();
+        let finished = res.attributes.iter().find(|a| a.key == "finished").unwrap();
+        assert_eq!(finished.value, "true");
+        assert_eq!(
+            ranking(&deps.storage),
+            vec![
+                (1, "1".to_string()),
+                (2, "3".to_string()),
+                (3, "4".to_string()),
+                (4, "5".to_string()),
+            ]
+        );
+        assert_eq!(TOKEN_RARITY.load(&deps.storage, "5").unwrap().rank, 4);
+    }
+}
@@ -0,0 +1,71 @@
+use cosmwasm_std::{Deps, StdResult, Uint128};
+
+use crate::{
+    error::ContractError,
+    msg::{CheckRoyaltiesResponse, RoyaltiesInfoResponse},
+    state::{CollectionInfo, Cw721MetadataContract, Extension, COLLECTION_INFO, CONFIG},
+};
+
+/// Percentages can not exceed this, whatever maximum is configured.
+pub const MAX_ROYALTY_PERCENTAGE: u64 = 100;
+
+pub fn validate_percentage(percentage: u64, max: u64) -> Result<(), ContractError> {
+    if percentage > max {
+        return Err(ContractError::RoyaltyTooHigh { percentage, max });
+    }
+    Ok(())
+}
+
+/// Checks the royalty fields of a token to be minted.
+pub fn validate_royalties(deps: Deps, extension: &Extension) -> Result<(), ContractError> {
+    let metadata = match extension {
+        Some(metadata) => metadata,
+        None => return Ok(()),
+    };
+    if let Some(percentage) = metadata.royalty_percentage {
+        let config = CONFIG.load(deps.storage)?;
+        validate_percentage(percentage, config.max_royalty_percentage)?;
+    }
+    if let Some(address) = &metadata.royalty_payment_address {
+        deps.api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+/// The royalty of a sale of `token_id`. Each field of the token falls back
+/// to the collection default, without both there is no royalty.
+pub fn query_royalty_info(
+    deps: Deps,
+    token_id: String,
+    sale_price: Uint128,
+) -> StdResult<RoyaltiesInfoResponse> {
+    let token = Cw721MetadataContract::default()
+        .tokens
+        .load(deps.storage, &token_id)?;
+    let CollectionInfo {
+        royalty_percentage,
+        royalty_payment_address,
+        ..
+    } = COLLECTION_INFO.load(deps.storage)?;
+    let metadata = token.extension.unwrap_or_default();
+
+    let percentage = metadata.royalty_percentage.or(royalty_percentage);
+    let address = metadata.royalty_payment_address.or(royalty_payment_address);
+    Ok(match (percentage, address) {
+        (Some(percentage), Some(address)) => RoyaltiesInfoResponse {
+            address,
+            royalty_amount: sale_price.multiply_ratio(percentage, 100u64),
+        },
+        _ => RoyaltiesInfoResponse {
+            address: String::new(),
+            royalty_amount: Uint128::zero(),
+        },
+    })
+}
+
+/// Royalties are set per token, marketplaces have to check each sale.
+pub fn check_royalties() -> CheckRoyaltiesResponse {
+    CheckRoyaltiesResponse {
+        royalty_payments: true,
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::{
+    coin, BankMsg, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult,
+    Storage, Timestamp,
+};
+use cw721_base::MintMsg;
+
+use crate::{
+    error::ContractError,
+    execute::execute_base,
+    helpers::ensure_creator,
+    msg::{MintPhasesResponse, MintStatusResponse},
+    state::{Cw721MetadataContract, MintPhase, ALLOWLIST, MINT_COUNTS, MINT_PHASES, PROCEEDS},
+    supply::next_token_id,
+    validation::validate_metadata,
+};
+
+fn load_phases(storage: &dyn Storage) -> StdResult<Vec<MintPhase>> {
+    Ok(MINT_PHASES.may_load(storage)?.unwrap_or_default())
+}
+
+fn current_phase(storage: &dyn Storage, time: Timestamp) -> StdResult<Option<MintPhase>> {
+    Ok(load_phases(storage)?
+        .into_iter()
+        .find(|phase| phase.start_time <= time && time < phase.end_time))
+}
+
+/// Denoms as accepted by the bank module: a letter followed by 2 to 127
+/// letters, digits or `/:._-`.
+fn is_valid_denom(denom: &str) -> bool {
+    let mut chars = denom.chars();
+    (3..=128).contains(&denom.len())
+        && chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c))
+}
+
+pub fn set_mint_phases(
+    deps: DepsMut,
+    info: MessageInfo,
+    mut phases: Vec<MintPhase>,
+) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+    phases.sort_by_key(|phase| phase.start_time);
+    let mut names = HashSet::new();
+    for (index, phase) in phases.iter().enumerate() {
+        if !names.insert(phase.name.as_str()) {
+            return Err(ContractError::DuplicateMintPhase {
+                name: phase.name.clone(),
+            });
+        }
+        if phase.end_time <= phase.start_time {
+            return Err(ContractError::InvalidMintPhase {
+                name: phase.name.clone(),
+            });
+        }
+        if phase.price.amount.is_zero() || !is_valid_denom(&phase.price.denom) {
+            return Err(ContractError::InvalidMintPrice {
+                name: phase.name.clone(),
+                price: phase.price.clone(),
+            });
+        }
+        if phase.per_address_limit == 0 {
+            return Err(ContractError::InvalidMintLimit {
+                name: phase.name.clone(),
+            });
+        }
+        validate_metadata(deps.as_ref(), &phase.extension)?;
+        if index > 0 && phase.start_time < phases[index - 1].end_time {
+            return Err(ContractError::MintPhasesOverlap {
+                name: phase.name.clone(),
+            });
+        }
+    }
+    MINT_PHASES.save(deps.storage, &phases)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_mint_phases")
+        .add_attribute("sender", info.sender)
+        .add_attribute("phases", phases.len().to_string()))
+}
+
+pub fn update_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+    for address in &add {
+        let address = deps.api.addr_validate(address)?;
+        ALLOWLIST.save(deps.storage, &address, &Empty {})?;
+    }
+    for address in &remove {
+        let address = deps.api.addr_validate(address)?;
+        ALLOWLIST.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_allowlist")
+        .add_attribute("sender", info.sender)
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+/// Mints a token with the next id and the token uri and metadata of the
+/// current phase to `owner`, or to the sender if not set, for the price of
+/// the phase.
+pub fn paid_mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: Option<String>,
+) -> Result<Response, ContractError> {
+    let phase =
+        current_phase(deps.storage, env.block.time)?.ok_or(ContractError::NoMintPhase {})?;
+    if phase.allowlist && !ALLOWLIST.has(deps.storage, &info.sender) {
+        return Err(ContractError::NotAllowlisted {
+            address: info.sender.into_string(),
+        });
+    }
+    let key = (phase.name.as_str(), &info.sender);
+    let minted = MINT_COUNTS.may_load(deps.storage, key)?.unwrap_or_default();
+    if minted >= phase.per_address_limit {
+        return Err(ContractError::MintLimitReached {
+            phase: phase.name,
+            limit: phase.per_address_limit,
+        });
+    }
+
+    // Everything paid beyond the price goes back to the sender.
+    let mut refund = info.funds.clone();
+    let paid = refund
+        .iter_mut()
+        .find(|coin| coin.denom == phase.price.denom)
+        .filter(|coin| coin.amount >= phase.price.amount)
+        .ok_or_else(|| ContractError::InsufficientFunds {
+            price: phase.price.clone(),
+        })?;
+    paid.amount -= phase.price.amount;
+    refund.retain(|coin| !coin.amount.is_zero());
+    MINT_COUNTS.save(deps.storage, key, &(minted + 1))?;
+    PROCEEDS.update(
+        deps.storage,
+        &phase.price.denom,
+        |proceeds| -> StdResult<_> { Ok(proceeds.unwrap_or_default() + phase.price.amount) },
+    )?;
+
+    // All tokens of a phase share its metadata, e.g. a placeholder the
+    // minter replaces with `UpdateMetadata` once the collection is revealed.
+    let mint = MintMsg {
+        token_id: next_token_id(deps.storage)?,
+        owner: owner.unwrap_or_else(|| info.sender.to_string()),
+        token_uri: phase.token_uri,
+        extension: phase.extension,
+    };
+    let minter = Cw721MetadataContract::default().minter.load(deps.storage)?;
+    let minter_info = MessageInfo {
+        sender: minter,
+        funds: vec![],
+    };
+    let msg = cw721_base::ExecuteMsg::Mint(mint);
+    let mut res = execute_base(deps, env, minter_info, msg)?
+        .add_attribute("buyer", info.sender.clone())
+        .add_attribute("phase", phase.name);
+    if !refund.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into_string(),
+            amount: refund,
+        });
+    }
+    Ok(res)
+}
+
+/// Sends the prices paid for mints, other funds of the contract stay.
+pub fn withdraw_proceeds(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender.clone(),
+    };
+    let amount = PROCEEDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+        .collect::<StdResult<Vec<Coin>>>()?;
+    for coin in &amount {
+        PROCEEDS.remove(deps.storage, &coin.denom);
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "withdraw_proceeds")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient.as_str());
+    if !amount.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: recipient.into_string(),
+            amount,
+        });
+    }
+    Ok(res)
+}
+
+pub fn query_mint_phases(deps: Deps, env: Env) -> StdResult<MintPhasesResponse> {
+    Ok(MintPhasesResponse {
+        phases: load_phases(deps.storage)?,
+        current: current_phase(deps.storage, env.block.time)?,
+    })
+}
+
+pub fn query_mint_status(
+    deps: Deps,
+    phase: String,
+    address: String,
+) -> StdResult<MintStatusResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(MintStatusResponse {
+        minted: MINT_COUNTS
+            .may_load(deps.storage, (&phase, &address))?
+            .unwrap_or_default(),
+        allowlisted: ALLOWLIST.has(deps.storage, &address),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::{
+        coins,
+        testing::{mock_dependencies, mock_env, mock_info},
+        CosmosMsg, Uint128,
+    };
+
+    use crate::execute::instantiate;
+    use crate::msg::InstantiateMsg;
+
+    const CREATOR: &str = "creator";
+
+    fn setup(mut deps: DepsMut, phases: Vec<MintPhase>) {
+        let msg = InstantiateMsg {
+            name: "Ark NFT Multichain".to_string(),
+            symbol: "Ark Protocol".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: None,
+            soulbound: None,
+            max_supply: None,
+            token_id_prefix: None,
+            token_id_padding: None,
+        };
+        instantiate(deps.branch(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        set_mint_phases(deps, mock_info(CREATOR, &[]), phases).unwrap();
+    }
+
+    /// A public phase of an hour from `start_time`, two mints per address.
+    fn phase(name: &str, price: Coin, start_time: Timestamp) -> MintPhase {
+        MintPhase {
+            name: name.to_string(),
+            allowlist: false,
+            start_time,
+            end_time: start_time.plus_seconds(3600),
+            price,
+            per_address_limit: 2,
+            token_uri: None,
+            extension: None,
+        }
+    }
+
+    fn bank_send(res: &Response) -> Option<(&str, &[Coin])> {
+        res.messages.iter().find_map(|msg| match &msg.msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                Some((to_address.as_str(), amount.as_slice()))
+            }
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn refund_excess_funds() {
+        let mut deps = mock_dependencies();
+        let start = mock_env().block.time;
+        setup(
+            deps.as_mut(),
+            vec![phase("public", coin(100, "uark"), start)],
+        );
+
+        // Nothing to refund when the price is paid exactly.
+        let info = mock_info("buyer", &coins(100, "uark"));
+        let res = paid_mint(deps.as_mut(), mock_env(), info, None).unwrap();
+        assert_eq!(bank_send(&res), None);
+
+        // Only the price is kept, whatever else was sent goes back.
+        let funds = [coin(10, "uatom"), coin(250, "uark")];
+        let info = mock_info("buyer", &funds);
+        let res = paid_mint(deps.as_mut(), mock_env(), info, None).unwrap();
+        assert_eq!(
+            bank_send(&res),
+            Some(("buyer", &[coin(10, "uatom"), coin(150, "uark")][..]))
+        );
+        assert_eq!(
+            PROCEEDS.load(&deps.storage, "uark").unwrap(),
+            Uint128::new(200)
+        );
+        assert!(!PROCEEDS.has(&deps.storage, "uatom"));
+
+        // A rejected mint adds nothing to the proceeds.
+        let info = mock_info("buyer", &coins(100, "uark"));
+        let err = paid_mint(deps.as_mut(), mock_env(), info, None).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::MintLimitReached { limit: 2, .. }
+        ));
+        assert_eq!(
+            PROCEEDS.load(&deps.storage, "uark").unwrap(),
+            Uint128::new(200)
+        );
+    }
+
+    #[test]
+    fn withdraw_all_proceeds() {
+        let mut deps = mock_dependencies();
+        let start = mock_env().block.time;
+        let later = start.plus_seconds(3600);
+        setup(
+            deps.as_mut(),
+            vec![
+                phase("early", coin(100, "uark"), start),
+                phase("late", coin(5, "uatom"), later),
+            ],
+        );
+        let info = mock_info("buyer", &coins(100, "uark"));
+        paid_mint(deps.as_mut(), mock_env(), info, None).unwrap();
+        let mut env = mock_env();
+        env.block.time = later;
+        let info = mock_info("buyer", &coins(5, "uatom"));
+        paid_mint(deps.as_mut(), env, info, None).unwrap();
+
+        let err = withdraw_proceeds(deps.as_mut(), mock_info("buyer", &[]), None).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Base(cw721_base::ContractError::Unauthorized {})
+        ));
+        let err = withdraw_proceeds(deps.as_mut(), mock_info(CREATOR, &[]), Some(String::new()))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        // Every denom goes to the creator if no recipient is given.
+        let res = withdraw_proceeds(deps.as_mut(), mock_info(CREATOR, &[]), None).unwrap();
+        assert_eq!(
+            bank_send(&res),
+            Some((CREATOR, &[coin(100, "uark"), coin(5, "uatom")][..]))
+        );
+        let res = withdraw_proceeds(deps.as_mut(), mock_info(CREATOR, &[]), None).unwrap();
+        assert_eq!(bank_send(&res), None);
+    }
+}
@@ -0,0 +1,197 @@
+use cosmwasm_std::{Deps, DepsMut, MessageInfo, Response, StdResult, Storage};
+
+use crate::{
+    error::ContractError,
+    helpers::ensure_minter,
+    metadata::remove_token,
+    state::{Cw721MetadataContract, Extension, CONFIG},
+    supply::record_burn,
+};
+
+fn token_soulbound(extension: &Extension) -> bool {
+    extension
+        .as_ref()
+        .and_then(|metadata| metadata.soulbound)
+        .unwrap_or_default()
+}
+
+fn is_soulbound(storage: &dyn Storage, extension: &Extension) -> StdResult<bool> {
+    Ok(token_soulbound(extension) || CONFIG.load(storage)?.soulbound)
+}
+
+/// Fails if updated metadata binds or releases the token, the flag is set
+/// for good on mint.
+pub fn ensure_soulbound_unchanged(
+    token_id: &str,
+    old: &Extension,
+    new: &Extension,
+) -> Result<(), ContractError> {
+    if token_soulbound(old) != token_soulbound(new) {
+        return Err(ContractError::SoulboundChanged {
+            token_id: token_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Fails for soulbound tokens.
+pub fn ensure_not_soulbound(
+    storage: &dyn Storage,
+    token_id: &str,
+    extension: &Extension,
+) -> Result<(), ContractError> {
+    if is_soulbound(storage, extension)? {
+        return Err(ContractError::Soulbound {
+            token_id: token_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects transfers and approvals of soulbound tokens, before they reach
+/// cw721-base.
+pub fn ensure_transferable(
+    deps: Deps,
+    msg: &cw721_base::ExecuteMsg<Extension>,
+) -> Result<(), ContractError> {
+    use cw721_base::ExecuteMsg as Base;
+    let token_id = match msg {
+        Base::TransferNft { token_id, .. }
+        | Base::SendNft { token_id, .. }
+        | Base::Approve { token_id, .. } => token_id,
+        Base::ApproveAll { .. } => {
+            if CONFIG.load(deps.storage)?.soulbound {
+                return Err(ContractError::SoulboundCollection {});
+            }
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    // Unknown tokens are left to cw721-base to reject.
+    if let Some(token) = Cw721MetadataContract::default()
+        .tokens
+        .may_load(deps.storage, token_id)?
+    {
+        ensure_not_soulbound(deps.storage, token_id, &token.extension)?;
+    }
+    Ok(())
+}
+
+pub fn revoke_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    let contract = Cw721MetadataContract::default();
+    let token = contract.tokens.load(deps.storage, &token_id)?;
+    if !is_soulbound(deps.storage, &token.extension)? {
+        return Err(ContractError::NotSoulbound { token_id });
+    }
+
+    contract.tokens.remove(deps.storage, &token_id)?;
+    contract.decrement_tokens(deps.storage)?;
+    remove_token(deps.storage, &token_id, &token.extension)?;
+    record_burn(deps.storage)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_token")
+        .add_attribute("sender", info.sender)
+        .add_attribute("owner", token.owner)
+        .add_attribute("token_id", token_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        to_binary,
+    };
+    use cw721_base::{ExecuteMsg, MintMsg};
+
+    use crate::execute::{execute, instantiate};
+    use crate::msg::InstantiateMsg;
+    use crate::state::Metadata;
+
+    const CREATOR: &str = "creator";
+
+    fn setup(mut deps: DepsMut, soulbound: Option<bool>) {
+        let msg = InstantiateMsg {
+            name: "Ark Badges".to_string(),
+            symbol: "ARKB".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: None,
+            soulbound,
+            max_supply: None,
+            token_id_prefix: None,
+            token_id_padding: None,
+        };
+        instantiate(deps.branch(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        for (token_id, soulbound) in [("badge", Some(true)), ("0001", None)] {
+            let msg = crate::msg::ExecuteMsg::Mint(MintMsg {
+                token_id: token_id.to_string(),
+                owner: "owner".to_string(),
+                token_uri: None,
+                extension: Some(Metadata {
+                    soulbound,
+                    ..Metadata::default()
+                }),
+            });
+            execute(deps.branch(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+        }
+    }
+
+    fn send_nft(token_id: &str) -> ExecuteMsg<Extension> {
+        ExecuteMsg::SendNft {
+            contract: "market".to_string(),
+            token_id: token_id.to_string(),
+            msg: to_binary("list").unwrap(),
+        }
+    }
+
+    fn approve_all() -> ExecuteMsg<Extension> {
+        ExecuteMsg::ApproveAll {
+            operator: "market".to_string(),
+            expires: None,
+        }
+    }
+
+    #[test]
+    fn soulbound_tokens_can_not_be_sent() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), None);
+
+        let err = ensure_transferable(deps.as_ref(), &send_nft("badge")).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { token_id } if token_id == "badge"));
+        ensure_transferable(deps.as_ref(), &send_nft("0001")).unwrap();
+        // Unknown tokens are rejected by cw721-base.
+        ensure_transferable(deps.as_ref(), &send_nft("0002")).unwrap();
+        // Operators can not move soulbound tokens either, so approving
+        // them is harmless.
+        ensure_transferable(deps.as_ref(), &approve_all()).unwrap();
+        let burn = ExecuteMsg::Burn {
+            token_id: "badge".to_string(),
+        };
+        ensure_transferable(deps.as_ref(), &burn).unwrap();
+    }
+
+    #[test]
+    fn soulbound_collection_blocks_every_token() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), Some(true));
+
+        let err = ensure_transferable(deps.as_ref(), &approve_all()).unwrap_err();
+        assert!(matches!(err, ContractError::SoulboundCollection {}));
+        // Also the tokens not marked soulbound themselves.
+        let err = ensure_transferable(deps.as_ref(), &send_nft("0001")).unwrap_err();
+        assert!(matches!(err, ContractError::Soulbound { token_id } if token_id == "0001"));
+        let revoke_all = ExecuteMsg::RevokeAll {
+            operator: "market".to_string(),
+        };
+        ensure_transferable(deps.as_ref(), &revoke_all).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
-use cosmwasm_std::Empty;
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -25,8 +26,145 @@ pub struct Metadata {
     pub background_color: Option<String>,
     pub animation_url: Option<String>,
     pub youtube_url: Option<String>,
+    /// Percentage of the sale price paid as royalty, see CW-2981. Falls back
+    /// to the collection default if not set.
+    pub royalty_percentage: Option<u64>,
+    /// Receiver of the royalties. Falls back to the collection default if
+    /// not set.
+    pub royalty_payment_address: Option<String>,
+    /// Makes the token non-transferable, even if the collection is not
+    /// soulbound. Set on mint, `UpdateMetadata` can not change it.
+    pub soulbound: Option<bool>,
 }
 
 pub type Extension = Option<Metadata>;
 
 pub type Cw721MetadataContract<'a> = cw721_base::Cw721Contract<'a, Extension, Empty>;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    /// Instantiator of the contract, the only one who can update the
+    /// collection info.
+    pub creator: Addr,
+    /// Royalty percentages above this are rejected.
+    pub max_royalty_percentage: u64,
+    /// Batches with more tokens are rejected.
+    pub max_batch_size: u32,
+    /// Makes all tokens non-transferable.
+    pub soulbound: bool,
+    /// No more tokens can be minted once this many were.
+    pub max_supply: Option<u64>,
+    /// Prefix of the ids assigned by `MintNext`.
+    pub token_id_prefix: String,
+    /// Minimum number of digits of the ids assigned by `MintNext`, padded
+    /// with zeros.
+    pub token_id_padding: u32,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+/// Describes the collection as a whole, for marketplaces and explorers.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct CollectionInfo {
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub banner_url: Option<String>,
+    pub external_link: Option<String>,
+    /// Whether the collection contains content not suitable for everyone.
+    #[serde(default)]
+    pub explicit_content: bool,
+    /// Marketplaces should not list tokens for sale before this time.
+    pub start_trading_time: Option<Timestamp>,
+    /// Royalty percentage of tokens that set none.
+    pub royalty_percentage: Option<u64>,
+    /// Royalty receiver of tokens that set none.
+    pub royalty_payment_address: Option<String>,
+}
+
+pub const COLLECTION_INFO: Item<CollectionInfo> = Item::new("collection_info");
+
+// Tokens minted and burned so far.
+pub const MINTED: Item<u64> = Item::new("minted");
+pub const BURNED: Item<u64> = Item::new("burned");
+// Number in the id of the last token minted by `MintNext`.
+pub const LAST_TOKEN_NUMBER: Item<u64> = Item::new("last_token_number");
+
+// Tokens whose metadata and token uri can no longer be updated.
+pub const FROZEN_TOKENS: Map<&str, Empty> = Map::new("frozen_tokens");
+// Set once the metadata of all tokens is frozen.
+pub const COLLECTION_FROZEN: Item<bool> = Item::new("collection_frozen");
+
+// Tokens by (trait_type, value) of their attributes.
+pub const TRAIT_TOKENS: Map<(&str, &str, &str), Empty> = Map::new("trait_tokens");
+// Number of tokens by (trait_type, value).
+pub const TRAIT_COUNTS: Map<(&str, &str), u64> = Map::new("trait_counts");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Rarity {
+    /// Sum of the inverse frequencies of the token's traits.
+    pub score: Decimal,
+    /// Position in the ranking, 1 is the rarest token.
+    pub rank: u64,
+}
+
+// Rarity of each token as of the last recomputation.
+pub const TOKEN_RARITY: Map<&str, Rarity> = Map::new("token_rarity");
+// Token ids by rank as of the last recomputation.
+pub const RARITY_RANKING: Map<u64, String> = Map::new("rarity_ranking");
+// Set when traits changed since the last recomputation.
+pub const RARITY_STALE: Item<bool> = Item::new("rarity_stale");
+// The recomputation in progress, if it needs more than one `UpdateRarity`.
+pub const RARITY_UPDATE: Item<RarityUpdate> = Item::new("rarity_update");
+// Scores of the recomputation in progress by (inverted score, token id), so
+// the rarest token comes first.
+pub const RARITY_SCORES: Map<(Vec<u8>, &str), Decimal> = Map::new("rarity_scores");
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RarityUpdate {
+    /// Number of tokens when the recomputation started.
+    pub total: u64,
+    /// Set when traits change before the recomputation is done.
+    pub changed: bool,
+    pub phase: RarityPhase,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RarityPhase {
+    /// Tokens after `start_after` are still to be scored.
+    Scoring { start_after: Option<String> },
+    /// Scored tokens are ranked from `rank` on.
+    Ranking { rank: u64 },
+    /// Ranks from `rank` on are left over from the last recomputation.
+    Cleanup { rank: u64 },
+}
+
+/// A period in which anyone, or only allowlisted addresses, can mint by
+/// paying `price`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MintPhase {
+    /// Mints are counted per phase name and address.
+    pub name: String,
+    /// Only allowlisted addresses can mint in this phase.
+    pub allowlist: bool,
+    pub start_time: Timestamp,
+    /// The phase is over at this time.
+    pub end_time: Timestamp,
+    pub price: Coin,
+    pub per_address_limit: u32,
+    /// Token uri of the tokens minted in this phase, e.g. a placeholder
+    /// until the minter reveals them.
+    pub token_uri: Option<String>,
+    /// Metadata of all tokens minted in this phase, e.g. a placeholder
+    /// until the minter reveals each token with `UpdateMetadata`.
+    pub extension: Extension,
+}
+
+// Mint phases ordered by start time.
+pub const MINT_PHASES: Item<Vec<MintPhase>> = Item::new("mint_phases");
+// Addresses that can mint in allowlist phases.
+pub const ALLOWLIST: Map<&Addr, Empty> = Map::new("allowlist");
+// Tokens minted by (phase name, address).
+pub const MINT_COUNTS: Map<(&str, &Addr), u32> = Map::new("mint_counts");
+// Prices paid for mints and not withdrawn yet, by denom.
+pub const PROCEEDS: Map<&str, Uint128> = Map::new("proceeds");
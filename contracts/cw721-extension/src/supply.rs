@@ -0,0 +1,166 @@
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+use cw721_base::MintMsg;
+use cw_storage_plus::Item;
+
+use crate::{
+    error::ContractError,
+    execute::execute_base,
+    helpers::ensure_minter,
+    msg::SupplyInfoResponse,
+    state::{Cw721MetadataContract, Extension, BURNED, CONFIG, LAST_TOKEN_NUMBER, MINTED},
+};
+
+fn load_counter(storage: &dyn Storage, counter: &Item<u64>) -> StdResult<u64> {
+    Ok(counter.may_load(storage)?.unwrap_or_default())
+}
+
+/// Fails if minting `count` more tokens would exceed the maximum supply.
+pub fn ensure_supply(storage: &dyn Storage, count: u64) -> Result<(), ContractError> {
+    if let Some(max_supply) = CONFIG.load(storage)?.max_supply {
+        if load_counter(storage, &MINTED)? + count > max_supply {
+            return Err(ContractError::MaxSupplyReached { max_supply });
+        }
+    }
+    Ok(())
+}
+
+pub fn record_mint(storage: &mut dyn Storage) -> StdResult<()> {
+    let minted = load_counter(storage, &MINTED)?;
+    MINTED.save(storage, &(minted + 1))
+}
+
+pub fn record_burn(storage: &mut dyn Storage) -> StdResult<()> {
+    let burned = load_counter(storage, &BURNED)?;
+    BURNED.save(storage, &(burned + 1))
+}
+
+/// The next free id, numbers already taken by an explicit `Mint` are
+/// skipped.
+pub fn next_token_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let config = CONFIG.load(storage)?;
+    let tokens = Cw721MetadataContract::default().tokens;
+    let mut number = load_counter(storage, &LAST_TOKEN_NUMBER)?;
+    loop {
+        number += 1;
+        let token_id = format!(
+            "{}{:0width$}",
+            config.token_id_prefix,
+            number,
+            width = config.token_id_padding as usize
+        );
+        if !tokens.has(storage, &token_id) {
+            LAST_TOKEN_NUMBER.save(storage, &number)?;
+            return Ok(token_id);
+        }
+    }
+}
+
+pub fn mint_next(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    token_uri: Option<String>,
+    extension: Extension,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.storage, &info.sender)?;
+    let token_id = next_token_id(deps.storage)?;
+    let msg = cw721_base::ExecuteMsg::Mint(MintMsg {
+        token_id,
+        owner,
+        token_uri,
+        extension,
+    });
+    execute_base(deps, env, info, msg)
+}
+
+pub fn query_supply_info(deps: Deps) -> StdResult<SupplyInfoResponse> {
+    let max_supply = CONFIG.load(deps.storage)?.max_supply;
+    let minted = load_counter(deps.storage, &MINTED)?;
+    Ok(SupplyInfoResponse {
+        max_supply,
+        minted,
+        burned: load_counter(deps.storage, &BURNED)?,
+        remaining: max_supply.map(|max_supply| max_supply.saturating_sub(minted)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    use crate::execute::{execute, instantiate};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    const CREATOR: &str = "creator";
+
+    fn setup(deps: DepsMut, token_id_prefix: Option<&str>, token_id_padding: Option<u32>) {
+        let msg = InstantiateMsg {
+            name: "Ark NFT Multichain".to_string(),
+            symbol: "Ark Protocol".to_string(),
+            minter: CREATOR.to_string(),
+            collection_info: None,
+            max_royalty_percentage: None,
+            max_batch_size: None,
+            soulbound: None,
+            max_supply: None,
+            token_id_prefix: token_id_prefix.map(String::from),
+            token_id_padding,
+        };
+        instantiate(deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    }
+
+    fn mint(deps: DepsMut, token_id: &str) {
+        let msg = ExecuteMsg::Mint(MintMsg {
+            token_id: token_id.to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: None,
+        });
+        execute(deps, mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn next_ids_skip_taken_ones() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), Some("ark-"), Some(3));
+        for token_id in ["ark-001", "ark-003", "ark-004", "ark-1"] {
+            mint(deps.as_mut(), token_id);
+        }
+
+        // The padding is part of the id, "ark-1" does not take number 1.
+        let ids: Vec<_> = (0..3)
+            .map(|_| next_token_id(&mut deps.storage).unwrap())
+            .collect();
+        assert_eq!(ids, vec!["ark-002", "ark-005", "ark-006"]);
+        assert_eq!(LAST_TOKEN_NUMBER.load(&deps.storage).unwrap(), 6);
+
+        // Numbers longer than the padding are not cut.
+        LAST_TOKEN_NUMBER.save(&mut deps.storage, &999).unwrap();
+        assert_eq!(next_token_id(&mut deps.storage).unwrap(), "ark-1000");
+    }
+
+    #[test]
+    fn next_ids_without_prefix_or_padding() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), None, None);
+        mint(deps.as_mut(), "1");
+        mint(deps.as_mut(), "01");
+
+        let info = mock_info(CREATOR, &[]);
+        let res = mint_next(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "owner".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        let token_id = res.attributes.iter().find(|a| a.key == "token_id").unwrap();
+        assert_eq!(token_id.value, "2");
+        assert_eq!(next_token_id(&mut deps.storage).unwrap(), "3");
+    }
+}
@@ -0,0 +1,89 @@
+use cosmwasm_std::{Deps, Empty, Order, StdResult, Storage};
+use cw721::TokensResponse;
+use cw_storage_plus::Bound;
+
+use crate::{
+    msg::{TraitValueCount, TraitValuesResponse},
+    rarity::mark_stale,
+    state::{Extension, TRAIT_COUNTS, TRAIT_TOKENS},
+};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+fn attributes(extension: &Extension) -> impl Iterator<Item = (&str, &str)> {
+    extension
+        .iter()
+        .flat_map(|metadata| metadata.attributes.iter().flatten())
+        .map(|attribute| (attribute.trait_type.as_str(), attribute.value.as_str()))
+}
+
+/// Adds the traits of a minted or updated token to the index.
+pub fn index_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    extension: &Extension,
+) -> StdResult<()> {
+    for (trait_type, value) in attributes(extension) {
+        TRAIT_TOKENS.save(storage, (trait_type, value, token_id), &Empty {})?;
+        TRAIT_COUNTS.update(storage, (trait_type, value), |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default() + 1)
+        })?;
+    }
+    mark_stale(storage)
+}
+
+/// Removes the traits of a burned token, or the old traits of an updated
+/// one, from the index.
+pub fn unindex_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    extension: &Extension,
+) -> StdResult<()> {
+    for (trait_type, value) in attributes(extension) {
+        TRAIT_TOKENS.remove(storage, (trait_type, value, token_id));
+        let count = TRAIT_COUNTS
+            .may_load(storage, (trait_type, value))?
+            .unwrap_or_default();
+        if count <= 1 {
+            TRAIT_COUNTS.remove(storage, (trait_type, value));
+        } else {
+            TRAIT_COUNTS.save(storage, (trait_type, value), &(count - 1))?;
+        }
+    }
+    mark_stale(storage)
+}
+
+pub fn query_tokens_by_trait(
+    deps: Deps,
+    trait_type: String,
+    value: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let tokens = TRAIT_TOKENS
+        .prefix((&trait_type, &value))
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+pub fn query_trait_values(
+    deps: Deps,
+    trait_type: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TraitValuesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let values = TRAIT_COUNTS
+        .prefix(&trait_type)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(value, count)| TraitValueCount { value, count }))
+        .collect::<StdResult<_>>()?;
+    Ok(TraitValuesResponse { trait_type, values })
+}
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use cosmwasm_std::Deps;
+
+use crate::{
+    error::ContractError,
+    royalties::validate_royalties,
+    state::{CollectionInfo, Extension, Metadata},
+};
+
+/// Schemes allowed for links to media and websites.
+pub const URI_SCHEMES: [&str; 3] = ["ipfs://", "ar://", "https://"];
+/// Maximum length of strings in metadata, in bytes.
+pub const MAX_STRING_LENGTH: usize = 1024;
+/// Maximum length of `image_data`, an svg image is more than a link.
+pub const MAX_IMAGE_DATA_LENGTH: usize = 16 * 1024;
+/// Maximum number of attributes of a token.
+pub const MAX_ATTRIBUTES: usize = 64;
+
+fn validate_length(field: &str, value: &str, max: usize) -> Result<(), ContractError> {
+    if value.len() > max {
+        return Err(ContractError::StringTooLong {
+            field: field.to_string(),
+            max,
+        });
+    }
+    Ok(())
+}
+
+fn validate_uri(field: &str, uri: &Option<String>) -> Result<(), ContractError> {
+    if let Some(uri) = uri {
+        validate_length(field, uri, MAX_STRING_LENGTH)?;
+        if !URI_SCHEMES.iter().any(|scheme| uri.starts_with(scheme)) {
+            return Err(ContractError::InvalidUriScheme {
+                field: field.to_string(),
+                uri: uri.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Colors are six hex digits without a leading `#`, as in OpenSea
+/// metadata.
+fn validate_background_color(color: &str) -> Result<(), ContractError> {
+    if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidBackgroundColor {
+            color: color.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_fields(metadata: &Metadata) -> Result<(), ContractError> {
+    validate_uri("image", &metadata.image)?;
+    validate_uri("animation_url", &metadata.animation_url)?;
+    validate_uri("external_url", &metadata.external_url)?;
+    if let Some(image_data) = &metadata.image_data {
+        validate_length("image_data", image_data, MAX_IMAGE_DATA_LENGTH)?;
+    }
+    for (field, value) in [
+        ("description", &metadata.description),
+        ("name", &metadata.name),
+        ("youtube_url", &metadata.youtube_url),
+        ("royalty_payment_address", &metadata.royalty_payment_address),
+    ] {
+        if let Some(value) = value {
+            validate_length(field, value, MAX_STRING_LENGTH)?;
+        }
+    }
+    if let Some(color) = &metadata.background_color {
+        validate_background_color(color)?;
+    }
+
+    let attributes = metadata.attributes.as_deref().unwrap_or_default();
+    if attributes.len() > MAX_ATTRIBUTES {
+        return Err(ContractError::TooManyAttributes {
+            count: attributes.len(),
+            max: MAX_ATTRIBUTES,
+        });
+    }
+    let mut trait_types = HashSet::new();
+    for attribute in attributes {
+        validate_length("trait_type", &attribute.trait_type, MAX_STRING_LENGTH)?;
+        validate_length("value", &attribute.value, MAX_STRING_LENGTH)?;
+        if let Some(display_type) = &attribute.display_type {
+            validate_length("display_type", display_type, MAX_STRING_LENGTH)?;
+        }
+        if !trait_types.insert(attribute.trait_type.as_str()) {
+            return Err(ContractError::DuplicateTraitType {
+                trait_type: attribute.trait_type.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the metadata of a token to be minted or updated.
+pub fn validate_metadata(deps: Deps, extension: &Extension) -> Result<(), ContractError> {
+    if let Some(metadata) = extension {
+        validate_fields(metadata)?;
+    }
+    validate_royalties(deps, extension)
+}
+
+/// Checks the links and texts of the collection info, its royalties are
+/// checked against the configured maximum separately.
+pub fn validate_collection_fields(collection_info: &CollectionInfo) -> Result<(), ContractError> {
+    validate_uri("image", &collection_info.image)?;
+    validate_uri("banner_url", &collection_info.banner_url)?;
+    validate_uri("external_link", &collection_info.external_link)?;
+    if let Some(description) = &collection_info.description {
+        validate_length("description", description, MAX_STRING_LENGTH)?;
+    }
+    Ok(())
+}
@@ -1,11 +1,18 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 
-use ibc_dispatcher::{
-    ibc_msg::{IbcExecuteMsg, IbcPingResponse},
-    msg::{ExecuteMsg, GetConnectionsResponse, GetCounterResponse, InstantiateMsg, QueryMsg},
+use ibc_dispatcher_base::{
+    events::DispatcherEvent,
+    ibc_msg::{
+        BatchResponse, DispatcherPacket, IbcExecuteMsg, IbcPingResponse, IbcSyncCounterResponse,
+    },
+    msg::{
+        ChannelHealthResponse, CounterSyncResponse, ExecuteMsg, GetConnectionsResponse,
+        GetCounterResponse, GetQueueResponse, GetUndeliveredAcksResponse, InstantiateMsg,
+        ListCountersResponse, QueryMsg, RejectedPacketsResponse,
+    },
 };
 
 fn main() {
@@ -17,8 +24,22 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema_with_title(
+        &schema_for!(DispatcherPacket<IbcExecuteMsg>),
+        &out_dir,
+        "DispatcherPacket",
+    );
     export_schema(&schema_for!(IbcExecuteMsg), &out_dir);
     export_schema(&schema_for!(IbcPingResponse), &out_dir);
+    export_schema(&schema_for!(IbcSyncCounterResponse), &out_dir);
+    export_schema(&schema_for!(BatchResponse), &out_dir);
     export_schema(&schema_for!(GetConnectionsResponse), &out_dir);
     export_schema(&schema_for!(GetCounterResponse), &out_dir);
+    export_schema(&schema_for!(ListCountersResponse), &out_dir);
+    export_schema(&schema_for!(CounterSyncResponse), &out_dir);
+    export_schema(&schema_for!(GetQueueResponse), &out_dir);
+    export_schema(&schema_for!(ChannelHealthResponse), &out_dir);
+    export_schema(&schema_for!(RejectedPacketsResponse), &out_dir);
+    export_schema(&schema_for!(GetUndeliveredAcksResponse), &out_dir);
+    export_schema(&schema_for!(DispatcherEvent), &out_dir);
 }
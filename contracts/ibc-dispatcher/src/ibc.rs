@@ -1,108 +0,0 @@
-#[cfg(not(feature = "library"))]
-use cosmwasm_std::entry_point;
-use cosmwasm_std::{
-    DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg,
-    IbcChannelOpenMsg, IbcOrder, IbcPacketTimeoutMsg,
-};
-
-use crate::{
-    state::{CONNECTIONS, COUNTERS},
-    ContractError,
-};
-pub const IBC_VERSION: &str = "ping-1";
-
-pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
-
-/// Handles the `OpenInit` and `OpenTry` parts of the IBC handshake.
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn ibc_channel_open(
-    _deps: DepsMut,
-    _env: Env,
-    msg: IbcChannelOpenMsg,
-) -> Result<(), ContractError> {
-    validate_order_and_version(msg.channel(), msg.counterparty_version())
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn ibc_channel_connect(
-    deps: DepsMut,
-    _env: Env,
-    msg: IbcChannelConnectMsg,
-) -> Result<IbcBasicResponse, ContractError> {
-    validate_order_and_version(msg.channel(), msg.counterparty_version())?;
-
-    // Initialize the count for this channel to zero.
-    let channel = msg.channel().endpoint.channel_id.clone();
-    CONNECTIONS.save(deps.storage, &channel, &true)?;
-    COUNTERS.save(deps.storage, &channel, &0)?;
-
-    Ok(IbcBasicResponse::new()
-        .add_attribute("method", "ibc_channel_connect")
-        .add_attribute("channel_id", channel))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn ibc_channel_close(
-    deps: DepsMut,
-    _env: Env,
-    msg: IbcChannelCloseMsg,
-) -> Result<IbcBasicResponse, ContractError> {
-    let channel = msg.channel().endpoint.channel_id.clone();
-    // Reset the state for the channel.
-    CONNECTIONS.remove(deps.storage, &channel);
-
-    Ok(IbcBasicResponse::new()
-        .add_attribute("method", "ibc_channel_close")
-        .add_attribute("channel", channel))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn ibc_packet_timeout(
-    _deps: DepsMut,
-    _env: Env,
-    _msg: IbcPacketTimeoutMsg,
-) -> Result<IbcBasicResponse, ContractError> {
-    // As with ack above, nothing to do here. If we cared about
-    // keeping track of state between the two chains then we'd want to
-    // respond to this likely as it means that the packet in question
-    // isn't going anywhere.
-    Ok(IbcBasicResponse::new().add_attribute("method", "ibc_packet_timeout"))
-}
-
-pub fn validate_order_and_version(
-    channel: &IbcChannel,
-    counterparty_version: Option<&str>,
-) -> Result<(), ContractError> {
-    // We expect an unordered channel here. Ordered channels have the
-    // property that if a message is lost the entire channel will stop
-    // working until you start it again.
-    if channel.order != IBC_ORDER {
-        return Err(ContractError::OrderedChannel {});
-    }
-
-    if channel.version != IBC_VERSION {
-        return Err(ContractError::InvalidVersion {
-            actual: channel.version.to_string(),
-            expected: IBC_VERSION.to_string(),
-        });
-    }
-
-    // Make sure that we're talking with a counterparty who speaks the
-    // same "protocol" as us.
-    //
-    // For a connection between chain A and chain B being established
-    // by chain A, chain B knows counterparty information during
-    // `OpenTry` and chain A knows counterparty information during
-    // `OpenAck`. We verify it when we have it but when we don't it's
-    // alright.
-    if let Some(counterparty_version) = counterparty_version {
-        if counterparty_version != IBC_VERSION {
-            return Err(ContractError::InvalidVersion {
-                actual: counterparty_version.to_string(),
-                expected: IBC_VERSION.to_string(),
-            });
-        }
-    }
-
-    Ok(())
-}
@@ -1,16 +1,21 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Order, Response,
-    StdResult,
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
 };
 use cw2::set_contract_version;
+use ibc_dispatcher_base::{
+    contract::query_connections,
+    ibc::{packet_sender, send_packet, SentPacket},
+    ibc_receive::{handle_packet, packet_reply, RECEIVE_REPLY_ID},
+};
 
 use crate::{
     error::ContractError,
+    ibc::PingHandler,
     ibc_msg::IbcExecuteMsg,
-    msg::{ExecuteMsg, GetConnectionsResponse, GetCounterResponse, InstantiateMsg, QueryMsg},
-    state::{CONNECTIONS, COUNTERS},
+    msg::{ExecuteMsg, GetCounterResponse, InstantiateMsg, QueryMsg},
+    state::COUNTERS,
 };
 
 const CONTRACT_NAME: &str = "crates.io:ap-ibc-example";
@@ -24,55 +29,68 @@ pub fn instantiate(
     _msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::new().add_attribute("method", "instantiate"))
+    Ok(Response::new())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    _deps: DepsMut,
+    deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Ping { channel } => ping(env, channel),
+        ExecuteMsg::Ping { channel } => ping(deps, env, info, channel),
+        ExecuteMsg::HandlePacket(msg) => handle_packet(&PingHandler, deps, env, info, msg),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        RECEIVE_REPLY_ID => Ok(packet_reply(deps, env, msg)?),
+        id => Err(ContractError::UnknownReply { id }),
     }
 }
 
-fn ping(env: Env, channel: String) -> Result<Response, ContractError> {
-    let msg = IbcMsg::SendPacket {
-        channel_id: channel.clone(),
-        data: to_binary(&IbcExecuteMsg::Ping {})?,
-        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(300)),
-    };
+fn ping(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+) -> Result<Response, ContractError> {
+    let sender = packet_sender(&env, &info);
+    let SentPacket { msg, event } =
+        send_packet(deps.storage, &env, &channel, sender, IbcExecuteMsg::Ping {})?;
 
     Ok(Response::new()
-        .add_attribute("method", "execute_ping")
         .add_attribute("channel", channel)
-        .add_message(msg))
+        .add_attribute("queued", msg.is_none().to_string())
+        .add_messages(msg)
+        .add_event(event))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetConnections {} => to_binary(&query_connections(deps)?),
+        QueryMsg::GetConnections {
+            start_after,
+            limit,
+            include_closed,
+        } => to_binary(&query_connections(
+            deps,
+            start_after,
+            limit,
+            include_closed.unwrap_or_default(),
+        )?),
         QueryMsg::GetCounter { channel } => to_binary(&query_counter(deps, channel)?),
     }
 }
 
-fn query_connections(deps: Deps) -> StdResult<GetConnectionsResponse> {
-    let connections: Vec<String> = CONNECTIONS
-        .keys(deps.storage, None, None, Order::Ascending)
-        .map(|x| x.unwrap_or_else(|_| "".to_string()))
-        .collect();
-    Ok(GetConnectionsResponse { connections })
-}
-
 fn query_counter(deps: Deps, channel: String) -> StdResult<GetCounterResponse> {
     let count = COUNTERS
-        .may_load(deps.storage, &channel)
-        .unwrap_or(Some(0))
-        .unwrap_or(0);
+        .may_load(deps.storage, &channel)?
+        .unwrap_or_default();
 
     Ok(GetCounterResponse { count })
 }
@@ -1,17 +1,14 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
-pub enum Never {}
-
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("only unordered channels are supported")]
-    OrderedChannel {},
+    #[error("{0}")]
+    Dispatcher(#[from] ibc_dispatcher_base::ContractError),
 
-    #[error("invalid IBC channel version. Got ({actual}), expected ({expected})")]
-    InvalidVersion { actual: String, expected: String },
+    #[error("unknown reply id ({id})")]
+    UnknownReply { id: u64 },
 }
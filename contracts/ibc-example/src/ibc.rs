@@ -1,108 +1,145 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg,
-    IbcChannelOpenMsg, IbcOrder, IbcPacketTimeoutMsg,
+    to_binary, DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, StdResult,
+};
+use ibc_dispatcher_base::{
+    ack::Ack,
+    handler::{PacketContext, PacketHandler},
+    ibc::{channel_close, channel_connect, channel_open, packet_timeout},
+    ibc_ack::packet_ack,
+    ibc_receive::packet_receive,
+    Never,
 };
 
 use crate::{
-    state::{CONNECTIONS, COUNTERS},
+    ibc_msg::{IbcExecuteMsg, IbcPingResponse},
+    state::COUNTERS,
     ContractError,
 };
+
+pub use ibc_dispatcher_base::ibc::IBC_ORDER;
+
 pub const IBC_VERSION: &str = "ping-1";
 
-pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
+/// Answers pings and counts the pongs received on each channel. Handshake,
+/// envelopes and acks are left to the dispatcher plumbing.
+pub struct PingHandler;
+
+impl PacketHandler for PingHandler {
+    const IBC_VERSION: &'static str = IBC_VERSION;
+    type Packet = IbcExecuteMsg;
+    type Error = ContractError;
+
+    fn receive(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        ctx: PacketContext,
+        packet: IbcExecuteMsg,
+    ) -> Result<IbcReceiveResponse, ContractError> {
+        match packet {
+            IbcExecuteMsg::Ping {} => Ok(IbcReceiveResponse::new()
+                .add_attribute("sender", ctx.sender.address)
+                .set_ack(to_binary(&IbcPingResponse {
+                    result: "pong".to_string(),
+                })?)),
+        }
+    }
+
+    fn ack(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        channel: String,
+        packet: IbcExecuteMsg,
+        ack: Ack,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        match packet {
+            IbcExecuteMsg::Ping {} => {
+                let IbcPingResponse { result } = match ack.into_typed()? {
+                    Ack::Result(res) => res,
+                    Ack::Error(e) => return Ok(IbcBasicResponse::new().add_attribute("error", e)),
+                };
+
+                if result != "pong" {
+                    return Ok(IbcBasicResponse::new()
+                        .add_attribute("error", format!("Not pong, Result is: {}", result)));
+                }
+
+                COUNTERS.update(deps.storage, &channel, |counter| -> StdResult<u32> {
+                    Ok(counter.unwrap_or_default() + 1)
+                })?;
+                Ok(IbcBasicResponse::new())
+            }
+        }
+    }
+
+    fn connect(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        channel: &IbcChannel,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        // Initialize the count for this channel to zero.
+        COUNTERS.save(deps.storage, &channel.endpoint.channel_id, &0)?;
+        Ok(IbcBasicResponse::new())
+    }
+}
 
 /// Handles the `OpenInit` and `OpenTry` parts of the IBC handshake.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_channel_open(
-    _deps: DepsMut,
-    _env: Env,
+    deps: DepsMut,
+    env: Env,
     msg: IbcChannelOpenMsg,
 ) -> Result<(), ContractError> {
-    validate_order_and_version(msg.channel(), msg.counterparty_version())
+    channel_open(&PingHandler, deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_channel_connect(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcChannelConnectMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    validate_order_and_version(msg.channel(), msg.counterparty_version())?;
-
-    // Initialize the count for this channel to zero.
-    let channel = msg.channel().endpoint.channel_id.clone();
-    CONNECTIONS.save(deps.storage, &channel, &true)?;
-    COUNTERS.save(deps.storage, &channel, &0)?;
-
-    Ok(IbcBasicResponse::new()
-        .add_attribute("method", "ibc_channel_connect")
-        .add_attribute("channel_id", channel))
+    channel_connect(&PingHandler, deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_channel_close(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcChannelCloseMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    let channel = msg.channel().endpoint.channel_id.clone();
-    // Reset the state for the channel.
-    CONNECTIONS.remove(deps.storage, &channel);
+    channel_close(&PingHandler, deps, env, msg)
+}
 
-    Ok(IbcBasicResponse::new()
-        .add_attribute("method", "ibc_channel_close")
-        .add_attribute("channel", channel))
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    packet_receive(&PingHandler, deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn ibc_packet_timeout(
-    _deps: DepsMut,
-    _env: Env,
-    _msg: IbcPacketTimeoutMsg,
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // As with ack above, nothing to do here. If we cared about
-    // keeping track of state between the two chains then we'd want to
-    // respond to this likely as it means that the packet in question
-    // isn't going anywhere.
-    Ok(IbcBasicResponse::new().add_attribute("method", "ibc_packet_timeout"))
+    packet_ack(&PingHandler, deps, env, msg)
 }
 
-pub fn validate_order_and_version(
-    channel: &IbcChannel,
-    counterparty_version: Option<&str>,
-) -> Result<(), ContractError> {
-    // We expect an unordered channel here. Ordered channels have the
-    // property that if a message is lost the entire channel will stop
-    // working until you start it again.
-    if channel.order != IBC_ORDER {
-        return Err(ContractError::OrderedChannel {});
-    }
-
-    if channel.version != IBC_VERSION {
-        return Err(ContractError::InvalidVersion {
-            actual: channel.version.to_string(),
-            expected: IBC_VERSION.to_string(),
-        });
-    }
-
-    // Make sure that we're talking with a counterparty who speaks the
-    // same "protocol" as us.
-    //
-    // For a connection between chain A and chain B being established
-    // by chain A, chain B knows counterparty information during
-    // `OpenTry` and chain A knows counterparty information during
-    // `OpenAck`. We verify it when we have it but when we don't it's
-    // alright.
-    if let Some(counterparty_version) = counterparty_version {
-        if counterparty_version != IBC_VERSION {
-            return Err(ContractError::InvalidVersion {
-                actual: counterparty_version.to_string(),
-                expected: IBC_VERSION.to_string(),
-            });
-        }
-    }
-
-    Ok(())
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    packet_timeout(&PingHandler, deps, env, msg)
 }
@@ -1,12 +1,10 @@
-pub mod ack;
 pub mod contract;
 mod error;
 pub mod ibc;
-pub mod ibc_ack;
 pub mod ibc_msg;
-pub mod ibc_receive;
 pub mod msg;
 pub mod state;
-pub mod tests;
+#[cfg(test)]
+mod tests;
 
 pub use crate::error::ContractError;
@@ -1,29 +1,43 @@
+use ibc_dispatcher_base::handler::HandlePacketMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::ibc_msg::IbcExecuteMsg;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    Ping { channel: String },
+    Ping {
+        channel: String,
+    },
+    /// Handles a received packet. Only the contract itself can call this,
+    /// from `ibc_packet_receive`.
+    HandlePacket(HandlePacketMsg<IbcExecuteMsg>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    /// Channels ordered by id. Closed channels are only listed with
+    /// `include_closed`.
+    GetConnections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_closed: Option<bool>,
+    },
     // GetCount returns the current count as a json-encoded number
-    GetConnections {},
-    GetCounter { channel: String },
+    GetCounter {
+        channel: String,
+    },
 }
 
-// We define a custom struct for each query response
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct GetConnectionsResponse {
-    pub connections: Vec<String>,
-}
+// The connections are tracked by the dispatcher plumbing.
+pub use ibc_dispatcher_base::msg::{ConnectionResponse, GetConnectionsResponse};
 
+// We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct GetCounterResponse {
     pub count: u32,
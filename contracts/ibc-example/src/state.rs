@@ -1,5 +1,5 @@
 use cw_storage_plus::Map;
 
-// Mapping between connections and the counter on that connection.
-pub const CONNECTIONS: Map<&str, bool> = Map::new("connections");
+// Mapping between connections and the counter on that connection. The
+// connections themselves are tracked by the dispatcher plumbing.
 pub const COUNTERS: Map<&str, u32> = Map::new("counters");
@@ -0,0 +1,101 @@
+use cosmwasm_std::{from_slice, to_binary, Binary, IbcAcknowledgement, StdError, StdResult};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// IBC ACK. See:
+/// https://github.com/cosmos/cosmos-sdk/blob/f999b1ff05a4db4a338a855713864497bedd4396/proto/ibc/core/channel/v1/channel.proto#L141-L147
+///
+/// `Ack` is the ack as it is written to the chain, its result is the raw
+/// result data. `Ack<T>` holds the result decoded into `T`, see
+/// [`Ack::decode`].
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Ack<T = Binary> {
+    Result(T),
+    Error(String),
+}
+
+impl Ack {
+    pub fn success() -> StdResult<Binary> {
+        to_binary(&Ack::Result(Binary::from(b"1")))
+    }
+
+    pub fn success_data(data: &impl Serialize) -> StdResult<Binary> {
+        to_binary(&Ack::Result(to_binary(data)?))
+    }
+
+    // create a serialized error message
+    pub fn fail(err: impl Into<String>) -> StdResult<Binary> {
+        to_binary(&Ack::<Binary>::Error(err.into()))
+    }
+
+    /// Decodes the result data into `T`.
+    pub fn into_typed<T: DeserializeOwned>(self) -> StdResult<Ack<T>> {
+        match self {
+            Ack::Result(data) => Ok(Ack::Result(from_slice(&data)?)),
+            Ack::Error(err) => Ok(Ack::Error(err)),
+        }
+    }
+}
+
+impl<T> Ack<T> {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Ack::Result(_))
+    }
+
+    pub fn into_result(self) -> Result<T, String> {
+        match self {
+            Ack::Result(res) => Ok(res),
+            Ack::Error(err) => Err(err),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Ack<T> {
+    /// Decodes a serialized ack whose result data is `T`, e.g.
+    /// `Ack::<IbcPingResponse>::decode(&ack.data)`.
+    pub fn decode(ack: &[u8]) -> StdResult<Self> {
+        from_slice::<Ack>(ack)?.into_typed()
+    }
+}
+
+impl TryFrom<&IbcAcknowledgement> for Ack {
+    type Error = StdError;
+
+    fn try_from(ack: &IbcAcknowledgement) -> StdResult<Self> {
+        from_slice(&ack.data)
+    }
+}
+
+impl TryFrom<Ack> for IbcAcknowledgement {
+    type Error = StdError;
+
+    fn try_from(ack: Ack) -> StdResult<Self> {
+        Ok(IbcAcknowledgement::new(to_binary(&ack)?))
+    }
+}
+
+// Panicking helpers, to quickly get the response in tests.
+#[cfg(any(test, feature = "test-utils"))]
+impl<T> Ack<T> {
+    pub fn unwrap(self) -> T {
+        match self {
+            Ack::Result(data) => data,
+            Ack::Error(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn unwrap_err(self) -> String {
+        match self {
+            Ack::Result(_) => panic!("not an error"),
+            Ack::Error(err) => err,
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Ack {
+    pub fn parse<T: DeserializeOwned>(ack: Binary) -> T {
+        Ack::<T>::decode(&ack).unwrap().unwrap()
+    }
+}
@@ -0,0 +1,165 @@
+use cosmwasm_std::{
+    Addr, Attribute, DepsMut, Env, IbcReceiveResponse, MessageInfo, Response, Storage,
+};
+
+use crate::{
+    health::{ensure_dispatchable, validate_thresholds},
+    ibc::{contract_sender, send_packet, SentPacket},
+    ibc_msg::{AdminMsg, IbcExecuteMsg},
+    state::{Config, CONFIG, COUNTERPARTY_PORTS},
+    ContractError,
+};
+
+/// Refuses to send or handle packets while the dispatcher is paused.
+pub fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if CONFIG.load(storage)?.paused {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+/// Refuses `Execute` messages calling a contract that is not allowed. No
+/// contract is allowed unless the admin has set some.
+pub fn ensure_contract_allowed(
+    storage: &dyn Storage,
+    contract: &Addr,
+) -> Result<(), ContractError> {
+    let allowed = CONFIG.load(storage)?.allowed_contracts.unwrap_or_default();
+    if !allowed.contains(contract) {
+        return Err(ContractError::ContractNotAllowed {
+            contract: contract.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Runs an admin command of the local admin.
+pub fn execute_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: AdminMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let attributes = apply(deps, config, msg)?;
+    Ok(Response::new().add_attributes(attributes))
+}
+
+/// Sends an admin command to the dispatcher at the other end of `channel`,
+/// which has to have this contract's port configured as controller. Local
+/// admin only.
+pub fn send_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    msg: AdminMsg,
+    force: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_dispatchable(deps.storage, &channel, force)?;
+
+    let sender = contract_sender(&env);
+    let SentPacket { msg, event } = send_packet(
+        deps.storage,
+        &env,
+        &channel,
+        sender,
+        IbcExecuteMsg::Admin(msg),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("channel", channel)
+        .add_attribute("queued", msg.is_none().to_string())
+        .add_messages(msg)
+        .add_event(event))
+}
+
+/// Refuses admin packets unless the counterparty of `channel` is the
+/// controller.
+pub fn ensure_controller(storage: &dyn Storage, channel: &str) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    let port = COUNTERPARTY_PORTS.load(storage, channel)?;
+    if config.controller_port.as_ref() != Some(&port) {
+        return Err(ContractError::NotController { port });
+    }
+    Ok(())
+}
+
+/// Runs an admin command received on `channel`, if the counterparty of the
+/// channel is the controller.
+pub fn receive_admin(
+    deps: DepsMut,
+    channel: String,
+    msg: AdminMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    ensure_controller(deps.storage, &channel)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let attributes = apply(deps, config, msg)?;
+    Ok(IbcReceiveResponse::new()
+        .add_attribute("channel", channel)
+        .add_attributes(attributes))
+}
+
+fn apply(
+    deps: DepsMut,
+    mut config: Config,
+    msg: AdminMsg,
+) -> Result<Vec<Attribute>, ContractError> {
+    let command = match msg {
+        AdminMsg::UpdateConfig {
+            admin,
+            controller_port,
+            degraded_after,
+            unhealthy_after,
+        } => {
+            if let Some(admin) = admin {
+                config.admin = deps.api.addr_validate(&admin)?;
+            }
+            if let Some(port) = controller_port {
+                config.controller_port = Some(port);
+            }
+            config.degraded_after = degraded_after.unwrap_or(config.degraded_after);
+            config.unhealthy_after = unhealthy_after.unwrap_or(config.unhealthy_after);
+            validate_thresholds(config.degraded_after, config.unhealthy_after)?;
+            "update_config"
+        }
+        AdminMsg::Pause {} => {
+            config.paused = true;
+            "pause"
+        }
+        AdminMsg::Unpause {} => {
+            config.paused = false;
+            "unpause"
+        }
+        AdminMsg::SetAllowedContracts { contracts } => {
+            config.allowed_contracts = contracts
+                .map(|contracts| {
+                    contracts
+                        .iter()
+                        .map(|contract| deps.api.addr_validate(contract))
+                        .collect()
+                })
+                .transpose()?;
+            "set_allowed_contracts"
+        }
+        AdminMsg::SetLimits {
+            max_packet_bytes,
+            max_execute_depth,
+        } => {
+            config.max_packet_bytes = max_packet_bytes;
+            config.max_execute_depth = max_execute_depth;
+            "set_limits"
+        }
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(vec![Attribute::new("command", command)])
+}
@@ -0,0 +1,426 @@
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response,
+    StdResult, Timestamp,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+
+use crate::{
+    admin::{ensure_not_paused, execute_admin, send_admin},
+    dispatcher::Dispatcher,
+    error::ContractError,
+    health::{
+        channel_health, ensure_dispatchable, validate_thresholds, DEFAULT_DEGRADED_AFTER,
+        DEFAULT_UNHEALTHY_AFTER,
+    },
+    ibc::{packet_sender, send_packet, SentPacket},
+    ibc_batch::{clear_batch, handle_batch, reply_batch, validate_batch, BATCH_REPLY_ID},
+    ibc_forward::forward_msg,
+    ibc_msg::IbcExecuteMsg,
+    ibc_receive::{handle_packet, packet_reply, RECEIVE_REPLY_ID},
+    msg::{
+        ChannelHealthResponse, ConnectionResponse, CounterResponse, CounterSyncResponse,
+        ExecuteMsg, GetConnectionsResponse, GetCounterResponse, GetQueueResponse,
+        GetUndeliveredAcksResponse, InstantiateMsg, ListCountersResponse, QueryMsg,
+        QueuedPacketResponse, RejectedPacketsResponse, UndeliveredAckResponse,
+    },
+    queue::{drop_stale, queued_packets},
+    state::{
+        Config, ForwardOrigin, ACTIVE_CONNECTIONS, CONFIG, CONNECTIONS, COUNTERS, REJECTED_PACKETS,
+        REMOTE_COUNTERS, UNDELIVERED_ACKS,
+    },
+};
+
+const CONTRACT_NAME: &str = "crates.io:ap-ibc-dispatcher";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    let degraded_after = msg.degraded_after.unwrap_or(DEFAULT_DEGRADED_AFTER);
+    let unhealthy_after = msg.unhealthy_after.unwrap_or(DEFAULT_UNHEALTHY_AFTER);
+    validate_thresholds(degraded_after, unhealthy_after)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            admin,
+            degraded_after,
+            unhealthy_after,
+            controller_port: msg.controller_port,
+            paused: false,
+            allowed_contracts: None,
+            max_packet_bytes: msg.max_packet_bytes,
+            max_execute_depth: msg.max_execute_depth,
+        },
+    )?;
+
+    Ok(Response::new())
+}
+
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Ping {
+            channel,
+            payload,
+            expect_echo,
+        } => ping(
+            deps,
+            env,
+            info,
+            channel,
+            payload,
+            expect_echo.unwrap_or_default(),
+        ),
+        ExecuteMsg::SyncCounter { channel, force } => {
+            sync_counter(deps, env, info, channel, force.unwrap_or_default())
+        }
+        ExecuteMsg::Forward { path, inner, force } => {
+            forward(deps, env, info, path, inner, force.unwrap_or_default())
+        }
+        ExecuteMsg::DropQueued {
+            channel,
+            queued_before,
+        } => drop_queued(deps, info, channel, queued_before),
+        ExecuteMsg::Batch {
+            channel,
+            msgs,
+            atomic,
+            force,
+        } => batch(
+            deps,
+            env,
+            info,
+            channel,
+            msgs,
+            atomic,
+            force.unwrap_or_default(),
+        ),
+        ExecuteMsg::Admin(msg) => execute_admin(deps, info, msg),
+        ExecuteMsg::SendAdmin {
+            channel,
+            msg,
+            force,
+        } => send_admin(deps, env, info, channel, msg, force.unwrap_or_default()),
+        ExecuteMsg::HandlePacket(msg) => handle_packet(&Dispatcher, deps, env, info, msg),
+        ExecuteMsg::HandleBatch { first, msgs } => handle_batch(deps, env, info, first, msgs),
+    }
+}
+
+fn ping(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    payload: Option<Binary>,
+    expect_echo: bool,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let sender = packet_sender(&env, &info);
+    let ping = IbcExecuteMsg::Ping {
+        payload,
+        expect_echo,
+    };
+    let SentPacket { msg, event } = send_packet(deps.storage, &env, &channel, sender, ping)?;
+
+    Ok(Response::new()
+        .add_attribute("channel", channel)
+        .add_attribute("queued", msg.is_none().to_string())
+        .add_messages(msg)
+        .add_event(event))
+}
+
+fn sync_counter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    force: bool,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    ensure_dispatchable(deps.storage, &channel, force)?;
+    let sender = packet_sender(&env, &info);
+    let SentPacket { msg, event } = send_packet(
+        deps.storage,
+        &env,
+        &channel,
+        sender,
+        IbcExecuteMsg::SyncCounter {},
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("channel", channel)
+        .add_attribute("queued", msg.is_none().to_string())
+        .add_messages(msg)
+        .add_event(event))
+}
+
+fn forward(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    path: Vec<String>,
+    inner: IbcExecuteMsg,
+    force: bool,
+) -> Result<Response, ContractError> {
+    if !inner.is_forwardable() {
+        return Err(ContractError::InvalidForward {});
+    }
+    if let IbcExecuteMsg::Batch { msgs, .. } = &inner {
+        validate_batch(msgs)?;
+    }
+    ensure_not_paused(deps.storage)?;
+    if let Some(channel) = path.first() {
+        ensure_dispatchable(deps.storage, channel, force)?;
+    }
+
+    let origin = ForwardOrigin::Local {
+        inner: inner.clone(),
+    };
+    let sender = packet_sender(&env, &info);
+    let SentPacket { msg, event } = forward_msg(deps.storage, &env, sender, &path, inner, origin)?;
+
+    Ok(Response::new()
+        .add_attribute("channel", &path[0])
+        .add_attribute("hops", path.len().to_string())
+        .add_attribute("queued", msg.is_none().to_string())
+        .add_messages(msg)
+        .add_event(event))
+}
+
+fn drop_queued(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    queued_before: Timestamp,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let dropped = drop_stale(deps.storage, &channel, queued_before)?;
+
+    Ok(Response::new()
+        .add_attribute("channel", channel)
+        .add_attribute("dropped", dropped.to_string()))
+}
+
+fn batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    msgs: Vec<IbcExecuteMsg>,
+    atomic: bool,
+    force: bool,
+) -> Result<Response, ContractError> {
+    validate_batch(&msgs)?;
+    ensure_not_paused(deps.storage)?;
+    ensure_dispatchable(deps.storage, &channel, force)?;
+
+    let items = msgs.len();
+    let sender = packet_sender(&env, &info);
+    let SentPacket { msg, event } = send_packet(
+        deps.storage,
+        &env,
+        &channel,
+        sender,
+        IbcExecuteMsg::Batch { msgs, atomic },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("channel", channel)
+        .add_attribute("items", items.to_string())
+        .add_attribute("queued", msg.is_none().to_string())
+        .add_messages(msg)
+        .add_event(event))
+}
+
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        RECEIVE_REPLY_ID => {
+            clear_batch(deps.storage);
+            packet_reply(deps, env, msg)
+        }
+        id if id >= BATCH_REPLY_ID => reply_batch(deps, (id - BATCH_REPLY_ID) as usize, msg.result),
+        id => Err(ContractError::UnknownReply { id }),
+    }
+}
+
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConnections {
+            start_after,
+            limit,
+            include_closed,
+        } => to_binary(&query_connections(
+            deps,
+            start_after,
+            limit,
+            include_closed.unwrap_or_default(),
+        )?),
+        QueryMsg::GetCounter { channel } => to_binary(&query_counter(deps, channel)?),
+        QueryMsg::GetCounterSync { channel } => to_binary(&query_counter_sync(deps, channel)?),
+        QueryMsg::GetChannelHealth { channel } => to_binary(&query_channel_health(deps, channel)?),
+        QueryMsg::GetRejectedPackets { channel } => {
+            to_binary(&query_rejected_packets(deps, channel)?)
+        }
+        QueryMsg::ListCounters { start_after, limit } => {
+            to_binary(&query_list_counters(deps, start_after, limit)?)
+        }
+        QueryMsg::GetQueue {
+            channel,
+            start_after,
+            limit,
+        } => to_binary(&query_queue(deps, channel, start_after, limit)?),
+        QueryMsg::GetUndeliveredAcks {
+            channel,
+            start_after,
+            limit,
+        } => to_binary(&query_undelivered_acks(deps, channel, start_after, limit)?),
+    }
+}
+
+pub fn query_connections(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    include_closed: bool,
+) -> StdResult<GetConnectionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let connections = if include_closed {
+        CONNECTIONS
+    } else {
+        ACTIVE_CONNECTIONS
+    };
+    let connections = connections
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|x| x.map(|(channel, state)| ConnectionResponse { channel, state }))
+        .collect::<StdResult<_>>()?;
+    Ok(GetConnectionsResponse { connections })
+}
+
+fn query_counter(deps: Deps, channel: String) -> StdResult<GetCounterResponse> {
+    let count = COUNTERS
+        .may_load(deps.storage, &channel)?
+        .unwrap_or_default();
+
+    Ok(GetCounterResponse { count })
+}
+
+fn query_counter_sync(deps: Deps, channel: String) -> StdResult<CounterSyncResponse> {
+    let local_count = query_counter(deps, channel.clone())?.count;
+    let remote = REMOTE_COUNTERS.may_load(deps.storage, &channel)?;
+    Ok(CounterSyncResponse {
+        channel,
+        local_count,
+        remote_count: remote.as_ref().map(|remote| remote.count),
+        difference: remote
+            .as_ref()
+            .map(|remote| i64::from(remote.count) - i64::from(local_count)),
+        synced_at: remote.map(|remote| remote.synced_at),
+    })
+}
+
+fn query_channel_health(deps: Deps, channel: String) -> StdResult<ChannelHealthResponse> {
+    let health = channel_health(deps.storage, &channel)?;
+    Ok(ChannelHealthResponse {
+        channel,
+        status: health.status,
+        consecutive_timeouts: health.consecutive_timeouts,
+        consecutive_errors: health.consecutive_errors,
+        corruptions: health.corruptions,
+    })
+}
+
+fn query_rejected_packets(deps: Deps, channel: String) -> StdResult<RejectedPacketsResponse> {
+    let rejected = REJECTED_PACKETS
+        .may_load(deps.storage, &channel)?
+        .unwrap_or_default();
+    Ok(RejectedPacketsResponse {
+        channel,
+        packet_too_large: rejected.packet_too_large,
+        execute_too_deep: rejected.execute_too_deep,
+        contract_not_allowed: rejected.contract_not_allowed,
+    })
+}
+
+fn query_list_counters(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListCountersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let counters = COUNTERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|x| {
+            let (channel, count) = x?;
+            let state = CONNECTIONS.load(deps.storage, &channel)?;
+            Ok(CounterResponse {
+                channel,
+                count,
+                state,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ListCountersResponse { counters })
+}
+
+fn query_queue(
+    deps: Deps,
+    channel: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GetQueueResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let packets = queued_packets(deps.storage, &channel, start_after, limit)?
+        .into_iter()
+        .map(|(id, queued)| {
+            Ok(QueuedPacketResponse {
+                id,
+                packet: from_binary(&queued.data)?,
+                queued_at: queued.queued_at,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(GetQueueResponse { packets })
+}
+
+fn query_undelivered_acks(
+    deps: Deps,
+    channel: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GetUndeliveredAcksResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let acks = UNDELIVERED_ACKS
+        .prefix(&channel)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|x| x.map(|(id, ack)| UndeliveredAckResponse { id, ack }))
+        .collect::<StdResult<_>>()?;
+    Ok(GetUndeliveredAcksResponse { acks })
+}
@@ -0,0 +1,383 @@
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcReceiveResponse, StdResult, WasmMsg,
+};
+
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ack::Ack,
+    admin::{ensure_contract_allowed, ensure_controller, ensure_not_paused, receive_admin},
+    handler::{PacketContext, PacketHandler},
+    health::{record_failure, record_success, Failure},
+    ibc::IBC_VERSION,
+    ibc_batch::{receive_batch, validate_batch},
+    ibc_forward::{
+        ack_forward, ack_forward_ack, receive_forward, receive_forward_ack, timeout_forward,
+        timeout_forward_ack,
+    },
+    ibc_msg::{
+        BatchResponse, IbcExecuteMsg, IbcPingResponse, IbcSyncCounterResponse, PacketSender,
+    },
+    limits::check_packet,
+    state::{RemoteCounter, COUNTERS, RECEIVED_PINGS, REMOTE_COUNTERS},
+    ContractError,
+};
+
+/// Handler of the dispatcher's own packets: pings and routing envelopes.
+pub struct Dispatcher;
+
+impl PacketHandler for Dispatcher {
+    const IBC_VERSION: &'static str = IBC_VERSION;
+    type Packet = IbcExecuteMsg;
+    type Error = ContractError;
+
+    fn check(&self, deps: DepsMut, channel: &str, data: &[u8]) -> Result<(), ContractError> {
+        check_packet(deps, channel, data)
+    }
+
+    fn validate(
+        &self,
+        deps: Deps,
+        env: &Env,
+        ctx: &PacketContext,
+        packet: &IbcExecuteMsg,
+    ) -> Result<(), ContractError> {
+        // Admin packets have to get through to unpause, and final acks
+        // belong to packets sent before the pause.
+        if !matches!(
+            packet,
+            IbcExecuteMsg::Admin(_) | IbcExecuteMsg::ForwardAck { .. }
+        ) {
+            ensure_not_paused(deps.storage)?;
+        }
+        match packet {
+            IbcExecuteMsg::Execute { contract, .. } => {
+                ensure_executable(deps, env, contract)?;
+            }
+            IbcExecuteMsg::Batch { msgs, .. } => {
+                validate_batch(msgs)?;
+                for msg in msgs {
+                    if let IbcExecuteMsg::Execute { contract, .. } = msg {
+                        ensure_executable(deps, env, contract)?;
+                    }
+                }
+            }
+            IbcExecuteMsg::Forward { path, inner, .. } => {
+                if !inner.is_forwardable() {
+                    return Err(ContractError::InvalidForward {});
+                }
+                // The final hop handles `inner` like a directly received
+                // packet.
+                if path.is_empty() {
+                    self.validate(deps, env, ctx, inner)?;
+                }
+            }
+            IbcExecuteMsg::Admin(_) => ensure_controller(deps.storage, &ctx.channel)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn receive(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        ctx: PacketContext,
+        packet: IbcExecuteMsg,
+    ) -> Result<IbcReceiveResponse, ContractError> {
+        let PacketContext { channel, sender } = ctx;
+        match packet {
+            IbcExecuteMsg::Ping {
+                payload,
+                expect_echo,
+            } => {
+                // Counted for `SyncCounter`, as counterpart of the pongs
+                // the sender counts.
+                RECEIVED_PINGS.update(deps.storage, &channel, |count| -> StdResult<u32> {
+                    Ok(count.unwrap_or_default() + 1)
+                })?;
+                receive_ping(deps, &sender, payload, expect_echo)
+            }
+            IbcExecuteMsg::SyncCounter {} => receive_sync_counter(deps, &channel),
+            // At the final hop `inner` is handled as if it had been sent
+            // directly by the original sender, pings are counted for the
+            // channel they arrive on.
+            IbcExecuteMsg::Forward { path, inner, .. } if path.is_empty() => {
+                self.receive(deps, env, PacketContext { channel, sender }, *inner)
+            }
+            IbcExecuteMsg::Forward { path, id, inner } => {
+                receive_forward(deps, env, channel, sender, path, id, *inner)
+            }
+            IbcExecuteMsg::ForwardAck { id, ack } => {
+                receive_forward_ack(deps, env, channel, id, ack)
+            }
+            IbcExecuteMsg::Execute { contract, msg } => receive_execute(deps, &env, contract, msg),
+            IbcExecuteMsg::Batch { msgs, atomic } => {
+                receive_batch(deps, env, channel, sender, msgs, atomic)
+            }
+            IbcExecuteMsg::Admin(msg) => receive_admin(deps, channel, msg),
+        }
+    }
+
+    fn ack(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        channel: String,
+        packet: IbcExecuteMsg,
+        ack: Ack,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        // Pings probe the channel, only they make it healthy again.
+        let event = match ack_failure(&packet, &ack) {
+            Some(failure) => record_failure(deps.storage, &channel, failure)?,
+            None => {
+                let restore = matches!(packet, IbcExecuteMsg::Ping { .. });
+                record_success(deps.storage, &channel, restore)?
+            }
+        };
+        let res = handle_ack(deps, env, channel, packet, ack)?;
+        Ok(res.add_events(event))
+    }
+
+    fn timeout(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        channel: String,
+        packet: IbcExecuteMsg,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        let event = record_failure(deps.storage, &channel, Failure::Timeout)?;
+        let res = match packet {
+            // A forwarded packet that times out at any hop is reported back to
+            // its origin as an error ack.
+            IbcExecuteMsg::Forward { id, .. } => timeout_forward(deps, env, channel, id)?,
+            // The previous hop still waits for the final ack.
+            IbcExecuteMsg::ForwardAck { id, ack } => {
+                timeout_forward_ack(deps, env, channel, id, ack)?
+            }
+            // As with ack above, nothing to do here. If we cared about
+            // keeping track of state between the two chains then we'd want to
+            // respond to this likely as it means that the packet in question
+            // isn't going anywhere.
+            _ => IbcBasicResponse::new(),
+        };
+        Ok(res.add_events(event))
+    }
+
+    fn connect(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        channel: &IbcChannel,
+    ) -> Result<IbcBasicResponse, ContractError> {
+        // Initialize the count for this channel to zero.
+        COUNTERS.save(deps.storage, &channel.endpoint.channel_id, &0)?;
+        Ok(IbcBasicResponse::new())
+    }
+}
+
+/// How the ack of `packet` has failed, if it has.
+fn ack_failure(packet: &IbcExecuteMsg, ack: &Ack) -> Option<Failure> {
+    match (packet, ack) {
+        (_, Ack::Error(_)) => Some(Failure::ErrorAck),
+        (
+            IbcExecuteMsg::Ping {
+                payload,
+                expect_echo,
+            },
+            Ack::Result(data),
+        ) => match from_binary(data) {
+            Ok(pong) if echo_intact(payload.as_ref(), *expect_echo, &pong) => None,
+            // A pong that can not be decoded is corrupted as well.
+            _ => Some(Failure::Corruption),
+        },
+        _ => None,
+    }
+}
+
+fn sha256(data: &[u8]) -> Binary {
+    Binary::from(Sha256::digest(data).as_slice())
+}
+
+/// Checks the pong against the payload of the ping: its hash has to match,
+/// and so does the echo if one was expected.
+pub fn echo_intact(payload: Option<&Binary>, expect_echo: bool, pong: &IbcPingResponse) -> bool {
+    let payload = match payload {
+        Some(payload) => payload,
+        None => return true,
+    };
+    let hash = sha256(payload);
+    let echo_intact = match &pong.payload {
+        Some(echo) => sha256(echo) == hash,
+        None => !expect_echo,
+    };
+    pong.sha256.as_ref() == Some(&hash) && echo_intact
+}
+
+pub fn receive_ping(
+    _deps: DepsMut,
+    sender: &PacketSender,
+    payload: Option<Binary>,
+    expect_echo: bool,
+) -> Result<IbcReceiveResponse, ContractError> {
+    Ok(IbcReceiveResponse::new()
+        .add_attribute("sender", &sender.address)
+        .add_attribute("sender_chain_id", &sender.chain_id)
+        .set_ack(to_binary(&IbcPingResponse {
+            result: "pong".to_string(),
+            sha256: payload.as_deref().map(sha256),
+            payload: payload.filter(|_| expect_echo),
+        })?))
+}
+
+pub fn receive_sync_counter(
+    deps: DepsMut,
+    channel: &str,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let count = RECEIVED_PINGS
+        .may_load(deps.storage, channel)?
+        .unwrap_or_default();
+    Ok(IbcReceiveResponse::new()
+        .add_attribute("count", count.to_string())
+        .set_ack(to_binary(&IbcSyncCounterResponse { count })?))
+}
+
+/// Refuses `Execute` messages calling a contract that is not allowed, or
+/// the dispatcher itself, which would run them with its own authority.
+fn ensure_executable(deps: Deps, env: &Env, contract: &str) -> Result<Addr, ContractError> {
+    let contract = deps.api.addr_validate(contract)?;
+    if contract == env.contract.address {
+        return Err(ContractError::ContractNotAllowed {
+            contract: contract.into_string(),
+        });
+    }
+    ensure_contract_allowed(deps.storage, &contract)?;
+    Ok(contract)
+}
+
+pub fn receive_execute(
+    deps: DepsMut,
+    env: &Env,
+    contract: String,
+    msg: Binary,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let contract = ensure_executable(deps.as_ref(), env, &contract)?;
+    Ok(IbcReceiveResponse::new()
+        .add_attribute("contract", &contract)
+        .add_message(WasmMsg::Execute {
+            contract_addr: contract.into_string(),
+            msg,
+            funds: vec![],
+        }))
+}
+
+pub fn handle_ack(
+    deps: DepsMut,
+    env: Env,
+    caller: String,
+    original_packet: IbcExecuteMsg,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    match original_packet {
+        IbcExecuteMsg::Ping {
+            payload,
+            expect_echo,
+        } => ack_ping(deps, env, caller, payload, expect_echo, res),
+        IbcExecuteMsg::SyncCounter {} => ack_sync_counter(deps, env, caller, res),
+        IbcExecuteMsg::Forward { path, id, .. } => ack_forward(deps, env, caller, path, id, res),
+        IbcExecuteMsg::ForwardAck { id, ack } => {
+            ack_forward_ack(deps.storage, caller, id, ack, res)
+        }
+        IbcExecuteMsg::Execute { .. } => {
+            Ok(IbcBasicResponse::new().add_attribute("success", res.is_ok().to_string()))
+        }
+        IbcExecuteMsg::Batch { .. } => ack_batch(res),
+        IbcExecuteMsg::Admin(_) => Ok(match res {
+            Ack::Result(_) => IbcBasicResponse::new(),
+            Ack::Error(e) => IbcBasicResponse::new().add_attribute("error", e),
+        }),
+    }
+}
+
+/// Decodes the result of an ack. Error acks and results that can not be
+/// decoded end up as `error` attribute, the ack is still handled so that
+/// the health of the channel is recorded.
+fn decode_ack<T: DeserializeOwned>(res: Ack) -> Result<T, IbcBasicResponse> {
+    let error = match res.into_typed() {
+        Ok(Ack::Result(res)) => return Ok(res),
+        Ok(Ack::Error(e)) => e,
+        Err(e) => format!("invalid ack: {}", e),
+    };
+    Err(IbcBasicResponse::new().add_attribute("error", error))
+}
+
+/// Stores the count of the counterparty next to the local one.
+pub fn ack_sync_counter(
+    deps: DepsMut,
+    env: Env,
+    caller: String,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    let IbcSyncCounterResponse { count } = match decode_ack(res) {
+        Ok(res) => res,
+        Err(res) => return Ok(res),
+    };
+    let counter = RemoteCounter {
+        count,
+        synced_at: env.block.time,
+    };
+    REMOTE_COUNTERS.save(deps.storage, &caller, &counter)?;
+
+    Ok(IbcBasicResponse::new().add_attribute("remote_count", count.to_string()))
+}
+
+pub fn ack_batch(res: Ack) -> Result<IbcBasicResponse, ContractError> {
+    let BatchResponse { results } = match decode_ack(res) {
+        Ok(res) => res,
+        Err(res) => return Ok(res),
+    };
+
+    let succeeded = results.iter().filter(|res| res.is_ok()).count();
+    Ok(IbcBasicResponse::new()
+        .add_attribute("succeeded", succeeded.to_string())
+        .add_attribute("failed", (results.len() - succeeded).to_string()))
+}
+
+pub fn ack_ping(
+    deps: DepsMut,
+    _env: Env,
+    caller: String,
+    payload: Option<Binary>,
+    expect_echo: bool,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    //Get the result from the ack, and make sure ack is success.
+    let pong: IbcPingResponse = match decode_ack(res) {
+        Ok(res) => res,
+        Err(res) => return Ok(res),
+    };
+
+    if !echo_intact(payload.as_ref(), expect_echo, &pong) {
+        return Ok(IbcBasicResponse::new().add_attribute("error", "corrupted payload"));
+    }
+
+    //Match the result to what we expect, if its a pong, save a counter.
+    match pong.result.as_str() {
+        "pong" => {
+            COUNTERS.update(deps.storage, &caller, |counter| -> StdResult<u32> {
+                match counter {
+                    Some(count) => Ok(count + 1),
+                    None => Ok(1),
+                }
+            })?;
+
+            Ok(IbcBasicResponse::new())
+        }
+        r => {
+            Ok(IbcBasicResponse::new()
+                .add_attribute("error", format!("Not pong, Result is: {}", r)))
+        }
+    }
+}
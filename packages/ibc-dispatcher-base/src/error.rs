@@ -0,0 +1,79 @@
+use cosmwasm_std::StdError;
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Never {}
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("only unordered channels are supported")]
+    OrderedChannel {},
+
+    #[error("invalid IBC channel version. Got ({actual}), expected ({expected})")]
+    InvalidVersion { actual: String, expected: String },
+
+    #[error("unauthorized")]
+    Unauthorized {},
+
+    #[error("no connection on channel ({channel})")]
+    UnknownChannel { channel: String },
+
+    #[error("channel ({channel}) is closed")]
+    ClosedChannel { channel: String },
+
+    #[error("channel ({channel}) is unhealthy, set force to send anyway")]
+    UnhealthyChannel { channel: String },
+
+    #[error("health thresholds must be above zero, the degraded one at most the unhealthy one")]
+    InvalidHealthThresholds {},
+
+    #[error("forward path must not be empty")]
+    EmptyForwardPath {},
+
+    #[error("only ping, execute and batch messages can be forwarded")]
+    InvalidForward {},
+
+    #[error("replayed packet from ({sender}), nonce ({nonce}) was already received")]
+    InvalidNonce { sender: String, nonce: u64 },
+
+    #[error("packet from ({sender}) too old, nonce ({nonce}) must be above ({oldest})")]
+    StaleNonce {
+        sender: String,
+        nonce: u64,
+        oldest: u64,
+    },
+
+    #[error("packet timed out on channel ({channel})")]
+    ForwardTimeout { channel: String },
+
+    #[error("batch must not be empty")]
+    EmptyBatch {},
+
+    #[error("batches can only contain ping and execute messages")]
+    InvalidBatchItem {},
+
+    #[error("dispatcher is paused")]
+    Paused {},
+
+    #[error("admin packets are only accepted from the controller, got port ({port})")]
+    NotController { port: String },
+
+    #[error("contract_not_allowed: contract ({contract}) is not allowed")]
+    ContractNotAllowed { contract: String },
+
+    #[error("packet_too_large: packet has ({size}) bytes, at most ({max}) are allowed")]
+    PacketTooLarge { size: usize, max: u32 },
+
+    #[error("execute_too_deep: execute message is nested ({depth}) levels deep, at most ({max}) are allowed")]
+    ExecuteTooDeep { depth: usize, max: u32 },
+
+    #[error("unknown reply id ({id})")]
+    UnknownReply { id: u64 },
+}
@@ -0,0 +1,168 @@
+use cosmwasm_std::Event;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Custom events emitted by the IBC plumbing, one per packet or channel
+/// action, and by the dispatcher on changes of channel health and on
+/// undelivered acks. wasmd prefixes the type with `wasm-`, `Send` is
+/// emitted as `wasm-dispatcher_send`. Every field is an attribute of the
+/// same name whose value is the field as string, the dispatcher's
+/// `schema/dispatcher_event.json` describes them all. Responses carry no
+/// `method` or `action` attribute, these events are the way to tell what
+/// happened.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatcherEvent {
+    /// A packet has been sent, or queued until its channel is open.
+    Send {
+        channel: String,
+        sender: String,
+        sender_chain_id: String,
+        nonce: u64,
+        queued: bool,
+    },
+    /// A packet has been received and is handed to the handler.
+    Receive {
+        channel: String,
+        sender: String,
+        sender_chain_id: String,
+        nonce: u64,
+        sequence: u64,
+    },
+    /// The ack of a sent packet has arrived.
+    Ack {
+        channel: String,
+        nonce: u64,
+        sequence: u64,
+        success: bool,
+    },
+    /// A sent packet has timed out.
+    Timeout {
+        channel: String,
+        nonce: u64,
+        sequence: u64,
+    },
+    /// The handshake of a channel has completed.
+    Open {
+        channel: String,
+        counterparty_port: String,
+        counterparty_channel: String,
+        version: String,
+    },
+    /// A channel has been closed, by either end.
+    Close { channel: String },
+    /// The health status of a channel has changed.
+    Health {
+        channel: String,
+        previous: String,
+        status: String,
+    },
+    /// The final ack of a forwarded packet could not be sent back along
+    /// `channel` and is kept in `GetUndeliveredAcks`.
+    UndeliveredAck {
+        channel: String,
+        id: u64,
+        attempts: u32,
+    },
+}
+
+impl DispatcherEvent {
+    /// The event type, without the `wasm-` prefix added by wasmd.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DispatcherEvent::Send { .. } => "dispatcher_send",
+            DispatcherEvent::Receive { .. } => "dispatcher_receive",
+            DispatcherEvent::Ack { .. } => "dispatcher_ack",
+            DispatcherEvent::Timeout { .. } => "dispatcher_timeout",
+            DispatcherEvent::Open { .. } => "dispatcher_open",
+            DispatcherEvent::Close { .. } => "dispatcher_close",
+            DispatcherEvent::Health { .. } => "dispatcher_health",
+            DispatcherEvent::UndeliveredAck { .. } => "dispatcher_undelivered_ack",
+        }
+    }
+}
+
+impl From<DispatcherEvent> for Event {
+    fn from(event: DispatcherEvent) -> Self {
+        let ty = event.event_type();
+        let attributes = match event {
+            DispatcherEvent::Send {
+                channel,
+                sender,
+                sender_chain_id,
+                nonce,
+                queued,
+            } => vec![
+                ("channel", channel),
+                ("sender", sender),
+                ("sender_chain_id", sender_chain_id),
+                ("nonce", nonce.to_string()),
+                ("queued", queued.to_string()),
+            ],
+            DispatcherEvent::Receive {
+                channel,
+                sender,
+                sender_chain_id,
+                nonce,
+                sequence,
+            } => vec![
+                ("channel", channel),
+                ("sender", sender),
+                ("sender_chain_id", sender_chain_id),
+                ("nonce", nonce.to_string()),
+                ("sequence", sequence.to_string()),
+            ],
+            DispatcherEvent::Ack {
+                channel,
+                nonce,
+                sequence,
+                success,
+            } => vec![
+                ("channel", channel),
+                ("nonce", nonce.to_string()),
+                ("sequence", sequence.to_string()),
+                ("success", success.to_string()),
+            ],
+            DispatcherEvent::Timeout {
+                channel,
+                nonce,
+                sequence,
+            } => vec![
+                ("channel", channel),
+                ("nonce", nonce.to_string()),
+                ("sequence", sequence.to_string()),
+            ],
+            DispatcherEvent::Open {
+                channel,
+                counterparty_port,
+                counterparty_channel,
+                version,
+            } => vec![
+                ("channel", channel),
+                ("counterparty_port", counterparty_port),
+                ("counterparty_channel", counterparty_channel),
+                ("version", version),
+            ],
+            DispatcherEvent::Close { channel } => vec![("channel", channel)],
+            DispatcherEvent::Health {
+                channel,
+                previous,
+                status,
+            } => vec![
+                ("channel", channel),
+                ("previous", previous),
+                ("status", status),
+            ],
+            DispatcherEvent::UndeliveredAck {
+                channel,
+                id,
+                attempts,
+            } => vec![
+                ("channel", channel),
+                ("id", id.to_string()),
+                ("attempts", attempts.to_string()),
+            ],
+        };
+        Event::new(ty).add_attributes(attributes)
+    }
+}
@@ -0,0 +1,128 @@
+use cosmwasm_std::{
+    Deps, DepsMut, Env, IbcBasicResponse, IbcChannel, IbcReceiveResponse, StdError,
+};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{ack::Ack, ibc_msg::PacketSender, ContractError};
+
+/// Application logic plugged into the dispatcher's IBC plumbing.
+///
+/// The plumbing runs the channel handshake, tracks connections, queues
+/// packets for channels that are still opening, wraps every packet into a
+/// `DispatcherPacket` envelope and writes the acks. An IBC app implements
+/// this trait and calls the generic functions in `ibc`, `ibc_receive` and
+/// `ibc_ack` from its entry points, see the `Dispatcher` handler for an
+/// example. Received packets are handled in a submessage the contract sends
+/// itself, so its `execute` has to pass `HandlePacket` to
+/// `ibc_receive::handle_packet` and its `reply` has to pass
+/// `ibc_receive::RECEIVE_REPLY_ID` to `ibc_receive::packet_reply`.
+pub trait PacketHandler {
+    /// Channel version both sides have to agree on during the handshake.
+    const IBC_VERSION: &'static str;
+
+    /// The application packet, sent as `msg` of the envelope.
+    type Packet: Serialize + DeserializeOwned;
+
+    /// Errors of the handler. On receive they are turned into error acks.
+    type Error: From<ContractError> + From<StdError> + ToString;
+
+    /// Checks a received packet before it is decoded and handled, `data` is
+    /// the raw packet. An error is written as error ack. Unlike in
+    /// `receive`, writes are kept on error, e.g. to count rejected packets.
+    fn check(&self, _deps: DepsMut, _channel: &str, _data: &[u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Checks a decoded packet before `receive` runs. Errors of `receive`
+    /// reach the reply with their text redacted by the chain, so errors
+    /// the sender has to see belong here: they are written to the error
+    /// ack as they are.
+    fn validate(
+        &self,
+        _deps: Deps,
+        _env: &Env,
+        _ctx: &PacketContext,
+        _packet: &Self::Packet,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Handles a received packet. The acknowledgement of the returned
+    /// response is the result data of the success ack, the plumbing wraps
+    /// it. An error is written as error ack and reverts all writes of the
+    /// handler. On chain the ack only holds the error code, see `validate`.
+    fn receive(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        ctx: PacketContext,
+        packet: Self::Packet,
+    ) -> Result<IbcReceiveResponse, Self::Error>;
+
+    /// Handles the ack of a packet sent along `channel`.
+    fn ack(
+        &self,
+        deps: DepsMut,
+        env: Env,
+        channel: String,
+        packet: Self::Packet,
+        ack: Ack,
+    ) -> Result<IbcBasicResponse, Self::Error>;
+
+    /// Handles a packet sent along `channel` that has timed out.
+    fn timeout(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        _channel: String,
+        _packet: Self::Packet,
+    ) -> Result<IbcBasicResponse, Self::Error> {
+        Ok(IbcBasicResponse::new())
+    }
+
+    /// Called once the handshake of `channel` has completed.
+    fn connect(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        _channel: &IbcChannel,
+    ) -> Result<IbcBasicResponse, Self::Error> {
+        Ok(IbcBasicResponse::new())
+    }
+
+    /// Called when `channel` is closed.
+    fn close(
+        &self,
+        _deps: DepsMut,
+        _env: Env,
+        _channel: &IbcChannel,
+    ) -> Result<IbcBasicResponse, Self::Error> {
+        Ok(IbcBasicResponse::new())
+    }
+}
+
+/// Where a received packet came from. `sender` is declared by the
+/// counterparty dispatcher, only its nonce has been checked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PacketContext {
+    /// The local channel the packet has been received on.
+    pub channel: String,
+    pub sender: PacketSender,
+}
+
+/// A received packet that has passed the checks of the plumbing. Its
+/// channel and sender are not part of the message, `handle_packet` takes
+/// them from the packet `ibc_packet_receive` is handling.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct HandlePacketMsg<T> {
+    pub msg: T,
+}
+
+/// Adds messages, attributes and events of a handler response to the one of
+/// the plumbing.
+pub(crate) fn merge_response(res: IbcBasicResponse, other: IbcBasicResponse) -> IbcBasicResponse {
+    res.add_submessages(other.messages)
+        .add_attributes(other.attributes)
+        .add_events(other.events)
+}
@@ -0,0 +1,119 @@
+use cosmwasm_std::{Event, StdResult, Storage};
+
+use crate::{
+    events::DispatcherEvent,
+    state::{ChannelHealth, HealthStatus, CHANNEL_HEALTH, CONFIG},
+    ContractError,
+};
+
+/// Consecutive failures after which a channel is degraded, unless
+/// configured at instantiation.
+pub const DEFAULT_DEGRADED_AFTER: u32 = 3;
+/// Consecutive failures after which a channel is unhealthy, unless
+/// configured at instantiation.
+pub const DEFAULT_UNHEALTHY_AFTER: u32 = 10;
+
+/// Why a packet sent on a channel has failed.
+pub enum Failure {
+    Timeout,
+    ErrorAck,
+    /// A pong whose payload hash does not match the ping, counted as error.
+    Corruption,
+}
+
+/// Checks the thresholds of the config: the channel degrades before, or when,
+/// it becomes unhealthy.
+pub fn validate_thresholds(degraded_after: u32, unhealthy_after: u32) -> Result<(), ContractError> {
+    if degraded_after == 0 || degraded_after > unhealthy_after {
+        return Err(ContractError::InvalidHealthThresholds {});
+    }
+    Ok(())
+}
+
+pub fn channel_health(storage: &dyn Storage, channel: &str) -> StdResult<ChannelHealth> {
+    Ok(CHANNEL_HEALTH
+        .may_load(storage, channel)?
+        .unwrap_or_default())
+}
+
+/// Refuses to dispatch on an unhealthy channel unless `force` is set.
+pub fn ensure_dispatchable(
+    storage: &dyn Storage,
+    channel: &str,
+    force: bool,
+) -> Result<(), ContractError> {
+    if !force && channel_health(storage, channel)?.status == HealthStatus::Unhealthy {
+        return Err(ContractError::UnhealthyChannel {
+            channel: channel.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Counts a failed packet and moves the channel to `Degraded` or
+/// `Unhealthy` once the configured thresholds are crossed. Returns the
+/// event of the change, if any.
+pub fn record_failure(
+    storage: &mut dyn Storage,
+    channel: &str,
+    failure: Failure,
+) -> StdResult<Option<Event>> {
+    let config = CONFIG.load(storage)?;
+    let mut health = channel_health(storage, channel)?;
+    match failure {
+        Failure::Timeout => health.consecutive_timeouts += 1,
+        Failure::ErrorAck => health.consecutive_errors += 1,
+        Failure::Corruption => {
+            health.consecutive_errors += 1;
+            health.corruptions += 1;
+        }
+    }
+
+    let failures = health.consecutive_timeouts + health.consecutive_errors;
+    let status = if failures >= config.unhealthy_after {
+        HealthStatus::Unhealthy
+    } else if failures >= config.degraded_after && health.status == HealthStatus::Healthy {
+        HealthStatus::Degraded
+    } else {
+        health.status.clone()
+    };
+    update(storage, channel, health, status)
+}
+
+/// Resets the failure counts after a successful ack. Only a successful
+/// ping (`restore`) makes the channel healthy again.
+pub fn record_success(
+    storage: &mut dyn Storage,
+    channel: &str,
+    restore: bool,
+) -> StdResult<Option<Event>> {
+    let mut health = channel_health(storage, channel)?;
+    health.consecutive_timeouts = 0;
+    health.consecutive_errors = 0;
+
+    let status = if restore {
+        HealthStatus::Healthy
+    } else {
+        health.status.clone()
+    };
+    update(storage, channel, health, status)
+}
+
+fn update(
+    storage: &mut dyn Storage,
+    channel: &str,
+    mut health: ChannelHealth,
+    status: HealthStatus,
+) -> StdResult<Option<Event>> {
+    let event = (health.status != status).then(|| {
+        DispatcherEvent::Health {
+            channel: channel.to_string(),
+            previous: health.status.as_str().to_string(),
+            status: status.as_str().to_string(),
+        }
+        .into()
+    });
+    health.status = status;
+    CHANNEL_HEALTH.save(storage, channel, &health)?;
+    Ok(event)
+}
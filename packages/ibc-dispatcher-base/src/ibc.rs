@@ -0,0 +1,266 @@
+use cosmwasm_std::{
+    from_slice, to_binary, DepsMut, Env, Event, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg, IbcOrder, IbcPacketTimeoutMsg, IbcTimeout,
+    MessageInfo, StdResult, Storage,
+};
+use serde::Serialize;
+
+use crate::{
+    dispatcher::Dispatcher,
+    events::DispatcherEvent,
+    handler::{merge_response, PacketHandler},
+    ibc_msg::{DispatcherPacket, PacketSender},
+    queue::{enqueue, flush},
+    state::{ChannelState, ACTIVE_CONNECTIONS, CONNECTIONS, COUNTERPARTY_PORTS, SEND_NONCES},
+    ContractError,
+};
+pub const IBC_VERSION: &str = "ping-1";
+
+pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
+
+/// Seconds until a packet sent by this contract times out.
+pub const PACKET_LIFETIME: u64 = 300;
+
+/// A packet created by `send_packet`.
+pub struct SentPacket {
+    /// The message sending the packet, `None` if it has been queued.
+    pub msg: Option<IbcMsg>,
+    /// The `dispatcher_send` event to add to the response.
+    pub event: Event,
+}
+
+/// Wraps `msg` into an envelope with the next nonce of `sender` and creates
+/// the packet sending it along `channel`. While the channel handshake is
+/// still running the packet is queued instead and no message is returned.
+pub fn send_packet<T: Serialize>(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    sender: PacketSender,
+    msg: T,
+) -> Result<SentPacket, ContractError> {
+    let state =
+        CONNECTIONS
+            .may_load(storage, channel)?
+            .ok_or_else(|| ContractError::UnknownChannel {
+                channel: channel.to_string(),
+            })?;
+
+    let key = (channel, sender.address.as_str());
+    let nonce = SEND_NONCES.may_load(storage, key)?.unwrap_or_default() + 1;
+    SEND_NONCES.save(storage, key, &nonce)?;
+
+    let event = DispatcherEvent::Send {
+        channel: channel.to_string(),
+        sender: sender.address.clone(),
+        sender_chain_id: sender.chain_id.clone(),
+        nonce,
+        queued: state == ChannelState::Opening,
+    };
+    let data = to_binary(&DispatcherPacket { sender, nonce, msg })?;
+    let msg = match state {
+        ChannelState::Opening => {
+            enqueue(storage, env, channel, data)?;
+            None
+        }
+        ChannelState::Open => Some(IbcMsg::SendPacket {
+            channel_id: channel.to_string(),
+            data,
+            timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(PACKET_LIFETIME)),
+        }),
+        ChannelState::Closed => {
+            return Err(ContractError::ClosedChannel {
+                channel: channel.to_string(),
+            })
+        }
+    };
+    Ok(SentPacket {
+        msg,
+        event: event.into(),
+    })
+}
+
+/// The account that triggered a packet on this chain.
+pub fn packet_sender(env: &Env, info: &MessageInfo) -> PacketSender {
+    PacketSender {
+        chain_id: env.block.chain_id.clone(),
+        address: info.sender.to_string(),
+    }
+}
+
+/// Sender of packets the contract sends on its own behalf.
+pub fn contract_sender(env: &Env) -> PacketSender {
+    PacketSender {
+        chain_id: env.block.chain_id.clone(),
+        address: env.contract.address.to_string(),
+    }
+}
+
+/// Handles the `OpenInit` and `OpenTry` parts of the IBC handshake.
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    channel_open(&Dispatcher, deps, env, msg)
+}
+
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    channel_connect(&Dispatcher, deps, env, msg)
+}
+
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    channel_close(&Dispatcher, deps, env, msg)
+}
+
+pub fn ibc_packet_timeout(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    packet_timeout(&Dispatcher, deps, env, msg)
+}
+
+/// Updates the state of a channel and the index of active channels.
+fn save_connection(storage: &mut dyn Storage, channel: &str, state: ChannelState) -> StdResult<()> {
+    match state {
+        ChannelState::Closed => ACTIVE_CONNECTIONS.remove(storage, channel),
+        _ => ACTIVE_CONNECTIONS.save(storage, channel, &state)?,
+    }
+    CONNECTIONS.save(storage, channel, &state)
+}
+
+pub fn channel_open<H: PacketHandler>(
+    _handler: &H,
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), H::Error> {
+    validate_order_and_version(msg.channel(), msg.counterparty_version(), H::IBC_VERSION)?;
+
+    // From now on the channel is known and packets for it are queued
+    // until the handshake completes.
+    let channel = msg.channel();
+    let id = &channel.endpoint.channel_id;
+    save_connection(deps.storage, id, ChannelState::Opening)?;
+    COUNTERPARTY_PORTS.save(deps.storage, id, &channel.counterparty_endpoint.port_id)?;
+
+    Ok(())
+}
+
+pub fn channel_connect<H: PacketHandler>(
+    handler: &H,
+    mut deps: DepsMut,
+    env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, H::Error> {
+    validate_order_and_version(msg.channel(), msg.counterparty_version(), H::IBC_VERSION)?;
+
+    let channel = msg.channel().endpoint.channel_id.clone();
+    save_connection(deps.storage, &channel, ChannelState::Open)?;
+    let event = DispatcherEvent::Open {
+        channel: channel.clone(),
+        counterparty_port: msg.channel().counterparty_endpoint.port_id.clone(),
+        counterparty_channel: msg.channel().counterparty_endpoint.channel_id.clone(),
+        version: msg.channel().version.clone(),
+    };
+
+    // Send everything that has been queued during the handshake.
+    let queued = flush(deps.storage, &env, &channel)?;
+    let res = handler.connect(deps.branch(), env, msg.channel())?;
+
+    Ok(merge_response(
+        IbcBasicResponse::new()
+            .add_messages(queued)
+            .add_event(event.into()),
+        res,
+    ))
+}
+
+pub fn channel_close<H: PacketHandler>(
+    handler: &H,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, H::Error> {
+    let channel = msg.channel().endpoint.channel_id.clone();
+    // Channel ids are never reused, keep the channel for the listing.
+    save_connection(deps.storage, &channel, ChannelState::Closed)?;
+    let res = handler.close(deps, env, msg.channel())?;
+    let event = DispatcherEvent::Close {
+        channel: channel.clone(),
+    };
+
+    Ok(merge_response(
+        IbcBasicResponse::new().add_event(event.into()),
+        res,
+    ))
+}
+
+pub fn packet_timeout<H: PacketHandler>(
+    handler: &H,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, H::Error> {
+    let channel = msg.packet.src.channel_id.clone();
+    let packet: DispatcherPacket<H::Packet> = from_slice(&msg.packet.data)?;
+    let event = DispatcherEvent::Timeout {
+        channel: channel.clone(),
+        nonce: packet.nonce,
+        sequence: msg.packet.sequence,
+    };
+    let res = handler.timeout(deps, env, channel, packet.msg)?;
+
+    Ok(merge_response(
+        IbcBasicResponse::new().add_event(event.into()),
+        res,
+    ))
+}
+
+pub fn validate_order_and_version(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+    version: &str,
+) -> Result<(), ContractError> {
+    // We expect an unordered channel here. Ordered channels have the
+    // property that if a message is lost the entire channel will stop
+    // working until you start it again.
+    if channel.order != IBC_ORDER {
+        return Err(ContractError::OrderedChannel {});
+    }
+
+    if channel.version != version {
+        return Err(ContractError::InvalidVersion {
+            actual: channel.version.to_string(),
+            expected: version.to_string(),
+        });
+    }
+
+    // Make sure that we're talking with a counterparty who speaks the
+    // same "protocol" as us.
+    //
+    // For a connection between chain A and chain B being established
+    // by chain A, chain B knows counterparty information during
+    // `OpenTry` and chain A knows counterparty information during
+    // `OpenAck`. We verify it when we have it but when we don't it's
+    // alright.
+    if let Some(counterparty_version) = counterparty_version {
+        if counterparty_version != version {
+            return Err(ContractError::InvalidVersion {
+                actual: counterparty_version.to_string(),
+                expected: version.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use cosmwasm_std::{from_slice, DepsMut, Env, IbcBasicResponse, IbcPacketAckMsg};
+
+use crate::{
+    ack::Ack, dispatcher::Dispatcher, events::DispatcherEvent, handler::PacketHandler,
+    ibc_msg::DispatcherPacket, ContractError,
+};
+
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    env: Env,
+    ack: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    packet_ack(&Dispatcher, deps, env, ack)
+}
+
+pub fn packet_ack<H: PacketHandler>(
+    handler: &H,
+    deps: DepsMut,
+    env: Env,
+    ack: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, H::Error> {
+    // This will get the ack from the ibc packet we sent,
+    // you either do pending things that you waited for confirmation on
+    // or register something based on the confirmation
+
+    // which local channel was this packet send from
+    let caller = ack.original_packet.src.channel_id.clone();
+    // we need to parse the ack based on our request
+    let original_packet: DispatcherPacket<H::Packet> = from_slice(&ack.original_packet.data)?;
+    let res = Ack::try_from(&ack.acknowledgement)?;
+    let event = DispatcherEvent::Ack {
+        channel: caller.clone(),
+        nonce: original_packet.nonce,
+        sequence: ack.original_packet.sequence,
+        success: res.is_ok(),
+    };
+
+    let res = handler.ack(deps, env, caller, original_packet.msg, res)?;
+    Ok(res.add_event(event.into()))
+}
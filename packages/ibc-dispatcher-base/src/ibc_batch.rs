@@ -0,0 +1,165 @@
+use cosmwasm_std::{
+    to_binary, Binary, DepsMut, Env, IbcReceiveResponse, MessageInfo, Response, StdResult, Storage,
+    SubMsg, SubMsgResult, WasmMsg,
+};
+
+use crate::{
+    ack::Ack,
+    dispatcher::Dispatcher,
+    handler::{PacketContext, PacketHandler},
+    ibc_msg::{BatchResponse, IbcExecuteMsg, PacketSender},
+    msg::ExecuteMsg,
+    state::{PendingBatch, PENDING_BATCH},
+    ContractError,
+};
+
+/// Reply id of the first message of a batch, the following messages use the
+/// ids after it.
+pub const BATCH_REPLY_ID: u64 = 1 << 32;
+
+/// Checks that a batch can be sent. Routing messages can not be batched,
+/// their acks are correlated by packet.
+pub fn validate_batch(msgs: &[IbcExecuteMsg]) -> Result<(), ContractError> {
+    if msgs.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if !msgs.iter().all(|msg| {
+        matches!(
+            msg,
+            IbcExecuteMsg::Ping { .. } | IbcExecuteMsg::Execute { .. }
+        )
+    }) {
+        return Err(ContractError::InvalidBatchItem {});
+    }
+    Ok(())
+}
+
+/// Runs the messages of a batch in submessages calling `HandleBatch`. An
+/// atomic batch runs in a single one, otherwise every message gets its own.
+/// Every message records its own ack, the reply of each submessage writes
+/// the results so far as ack of the packet.
+pub fn receive_batch(
+    deps: DepsMut,
+    env: Env,
+    channel: String,
+    sender: PacketSender,
+    msgs: Vec<IbcExecuteMsg>,
+    atomic: bool,
+) -> Result<IbcReceiveResponse, ContractError> {
+    validate_batch(&msgs)?;
+
+    let batch = PendingBatch {
+        channel,
+        sender,
+        atomic,
+        // Every message ends up with its own result, either from
+        // `handle_batch` or from the reply of its failed submessage.
+        results: vec![Ack::Error("not run".to_string()); msgs.len()],
+    };
+    PENDING_BATCH.save(deps.storage, &batch)?;
+
+    let items = msgs.len();
+    let handle = |first: usize, msgs| -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::HandleBatch {
+                first: first as u32,
+                msgs,
+            })?,
+            funds: vec![],
+        })
+    };
+    let submsgs = if atomic {
+        vec![SubMsg::reply_always(handle(0, msgs)?, BATCH_REPLY_ID)]
+    } else {
+        msgs.into_iter()
+            .enumerate()
+            .map(|(i, msg)| {
+                Ok(SubMsg::reply_always(
+                    handle(i, vec![msg])?,
+                    BATCH_REPLY_ID + i as u64,
+                ))
+            })
+            .collect::<StdResult<_>>()?
+    };
+
+    Ok(IbcReceiveResponse::new()
+        .add_submessages(submsgs)
+        .add_attribute("items", items.to_string())
+        .add_attribute("atomic", atomic.to_string()))
+}
+
+/// Runs messages of the batch being received, on behalf of its sender, and
+/// records the ack data of each, e.g. the pong of a ping. Like for a single
+/// packet, a message without ack data reports a plain success.
+pub fn handle_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    first: u32,
+    msgs: Vec<IbcExecuteMsg>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut batch = PENDING_BATCH
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    let mut res = Response::new();
+    for (i, msg) in msgs.into_iter().enumerate() {
+        let ctx = PacketContext {
+            channel: batch.channel.clone(),
+            sender: batch.sender.clone(),
+        };
+        let item = Dispatcher.receive(deps.branch(), env.clone(), ctx, msg)?;
+        let data = if item.acknowledgement.is_empty() {
+            Binary::from(b"1")
+        } else {
+            item.acknowledgement
+        };
+        let result = batch
+            .results
+            .get_mut(first as usize + i)
+            .ok_or(ContractError::Unauthorized {})?;
+        *result = Ack::Result(data);
+        res = res
+            .add_submessages(item.messages)
+            .add_attributes(item.attributes)
+            .add_events(item.events);
+    }
+    PENDING_BATCH.save(deps.storage, &batch)?;
+    Ok(res)
+}
+
+/// Forgets the batch once the packet holding it has been handled.
+pub fn clear_batch(storage: &mut dyn Storage) {
+    PENDING_BATCH.remove(storage);
+}
+
+/// Records the failure of the batch message `index`, if any, and writes the
+/// results so far as ack. A failed atomic batch has been rolled back
+/// completely.
+pub fn reply_batch(
+    deps: DepsMut,
+    index: usize,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let mut batch = PENDING_BATCH.load(deps.storage)?;
+    let mut res = Response::new();
+    if let SubMsgResult::Err(error) = result {
+        if batch.atomic {
+            batch.results.fill(Ack::Error(error));
+        } else if let Some(res) = batch.results.get_mut(index) {
+            *res = Ack::Error(error);
+        }
+        PENDING_BATCH.save(deps.storage, &batch)?;
+        res = res.add_attribute("item", index.to_string());
+    }
+
+    // Data set by a reply replaces the ack written on receive, the reply of
+    // the last message writes the complete results.
+    Ok(res.set_data(Ack::success_data(&BatchResponse {
+        results: batch.results,
+    })?))
+}
@@ -0,0 +1,233 @@
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, DepsMut, Env, IbcBasicResponse, IbcReceiveResponse, StdResult,
+    Storage,
+};
+
+use crate::{
+    ack::Ack,
+    dispatcher::handle_ack,
+    events::DispatcherEvent,
+    ibc::{contract_sender, send_packet, SentPacket},
+    ibc_msg::{IbcExecuteMsg, PacketSender},
+    state::{
+        ChannelState, ForwardOrigin, CONNECTIONS, FORWARDS, FORWARD_ACK_RETRIES, FORWARD_ID,
+        UNDELIVERED_ACKS,
+    },
+    ContractError,
+};
+
+/// Times a final ack that timed out on its way back is resent.
+pub const MAX_FORWARD_ACK_RETRIES: u32 = 3;
+
+fn next_forward_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = FORWARD_ID.may_load(storage)?.unwrap_or_default() + 1;
+    FORWARD_ID.save(storage, &id)?;
+    Ok(id)
+}
+
+/// Creates the packet sending `inner` along `path` and remembers where its
+/// final ack has to be delivered.
+pub fn forward_msg(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: PacketSender,
+    path: &[String],
+    inner: IbcExecuteMsg,
+    origin: ForwardOrigin,
+) -> Result<SentPacket, ContractError> {
+    let (channel, rest) = path
+        .split_first()
+        .ok_or(ContractError::EmptyForwardPath {})?;
+
+    let id = next_forward_id(storage)?;
+    let msg = IbcExecuteMsg::Forward {
+        path: rest.to_vec(),
+        id,
+        inner: Box::new(inner),
+    };
+    let packet = send_packet(storage, env, channel, sender, msg)?;
+    FORWARDS.save(storage, (channel, id), &origin)?;
+
+    Ok(packet)
+}
+
+/// Relays a `Forward` envelope to the next hop, the final hop is handled by
+/// the packet handler itself. The original sender is kept in the envelope
+/// of every hop.
+pub fn receive_forward(
+    deps: DepsMut,
+    env: Env,
+    channel: String,
+    sender: PacketSender,
+    path: Vec<String>,
+    id: u64,
+    inner: IbcExecuteMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let next = path[0].clone();
+    let SentPacket { msg, event } = forward_msg(
+        deps.storage,
+        &env,
+        sender,
+        &path,
+        inner,
+        ForwardOrigin::Hop { channel, id },
+    )?;
+
+    Ok(IbcReceiveResponse::new()
+        .add_attribute("next_channel", next)
+        .add_messages(msg)
+        .add_event(event))
+}
+
+/// Handles the final ack of a packet this contract has forwarded before.
+pub fn receive_forward_ack(
+    deps: DepsMut,
+    env: Env,
+    channel: String,
+    id: u64,
+    ack: Binary,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let res = resolve_forward(deps, env, &channel, id, from_binary(&ack)?)?;
+
+    Ok(IbcReceiveResponse::new()
+        .add_submessages(res.messages)
+        .add_attributes(res.attributes)
+        .add_events(res.events))
+}
+
+pub fn ack_forward(
+    deps: DepsMut,
+    env: Env,
+    channel: String,
+    path: Vec<String>,
+    id: u64,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    // With hops left, a successful ack only tells that the next dispatcher
+    // relayed the packet. The final ack arrives later as `ForwardAck`.
+    if path.is_empty() || matches!(res, Ack::Error(_)) {
+        return resolve_forward(deps, env, &channel, id, res);
+    }
+
+    Ok(IbcBasicResponse::new().add_attribute("forward_id", id.to_string()))
+}
+
+pub fn timeout_forward(
+    deps: DepsMut,
+    env: Env,
+    channel: String,
+    id: u64,
+) -> Result<IbcBasicResponse, ContractError> {
+    let error = ContractError::ForwardTimeout {
+        channel: channel.clone(),
+    };
+    resolve_forward(deps, env, &channel, id, Ack::Error(error.to_string()))
+}
+
+/// Resends a final ack that timed out on its way back, it may well be a
+/// success the origin has to learn about. After `MAX_FORWARD_ACK_RETRIES`
+/// resends it is kept as undelivered.
+pub fn timeout_forward_ack(
+    deps: DepsMut,
+    env: Env,
+    channel: String,
+    id: u64,
+    ack: Binary,
+) -> Result<IbcBasicResponse, ContractError> {
+    let retries = FORWARD_ACK_RETRIES
+        .may_load(deps.storage, (&channel, id))?
+        .unwrap_or_default();
+    let res = from_binary(&ack)?;
+    if retries >= MAX_FORWARD_ACK_RETRIES {
+        FORWARD_ACK_RETRIES.remove(deps.storage, (&channel, id));
+        return keep_undelivered(deps.storage, channel, id, res, retries + 1);
+    }
+    FORWARD_ACK_RETRIES.save(deps.storage, (&channel, id), &(retries + 1))?;
+    send_forward_ack(deps.storage, &env, channel, id, res)
+}
+
+/// Handles the ack of a final ack sent back to the previous hop. An error
+/// ack means the previous hop could not deliver it, so it is kept.
+pub fn ack_forward_ack(
+    storage: &mut dyn Storage,
+    channel: String,
+    id: u64,
+    ack: Binary,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    let attempts = FORWARD_ACK_RETRIES
+        .may_load(storage, (&channel, id))?
+        .unwrap_or_default()
+        + 1;
+    FORWARD_ACK_RETRIES.remove(storage, (&channel, id));
+    match res {
+        Ack::Result(_) => Ok(IbcBasicResponse::new()),
+        Ack::Error(_) => keep_undelivered(storage, channel, id, from_binary(&ack)?, attempts),
+    }
+}
+
+fn keep_undelivered(
+    storage: &mut dyn Storage,
+    channel: String,
+    id: u64,
+    res: Ack,
+    attempts: u32,
+) -> Result<IbcBasicResponse, ContractError> {
+    UNDELIVERED_ACKS.save(storage, (&channel, id), &res)?;
+    Ok(IbcBasicResponse::new().add_event(
+        DispatcherEvent::UndeliveredAck {
+            channel,
+            id,
+            attempts,
+        }
+        .into(),
+    ))
+}
+
+/// Sends a final ack one hop back. It is kept as undelivered if the
+/// channel is closed, nothing could be sent on it anymore.
+fn send_forward_ack(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: String,
+    id: u64,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    if CONNECTIONS.may_load(storage, &channel)? == Some(ChannelState::Closed) {
+        let attempts = FORWARD_ACK_RETRIES
+            .may_load(storage, (&channel, id))?
+            .unwrap_or_default();
+        FORWARD_ACK_RETRIES.remove(storage, (&channel, id));
+        return keep_undelivered(storage, channel, id, res, attempts);
+    }
+    let ack = IbcExecuteMsg::ForwardAck {
+        id,
+        ack: to_binary(&res)?,
+    };
+    let SentPacket { msg, event } = send_packet(storage, env, &channel, contract_sender(env), ack)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("channel", channel)
+        .add_messages(msg)
+        .add_event(event))
+}
+
+/// Delivers the final ack of a forwarded packet, either to the local ack
+/// handler or one hop back towards the origin.
+fn resolve_forward(
+    deps: DepsMut,
+    env: Env,
+    channel: &str,
+    id: u64,
+    res: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    let origin = FORWARDS.load(deps.storage, (channel, id))?;
+    FORWARDS.remove(deps.storage, (channel, id));
+
+    match origin {
+        ForwardOrigin::Local { inner } => handle_ack(deps, env, channel.to_string(), inner, res),
+        ForwardOrigin::Hop { channel, id } => {
+            send_forward_ack(deps.storage, &env, channel, id, res)
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use cosmwasm_std::Binary;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::ack::Ack;
+
+/// Envelope around every packet sent by a dispatcher.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DispatcherPacket<T> {
+    pub sender: PacketSender,
+    /// Increases with every packet of `sender` on the channel. The receiving
+    /// dispatcher accepts every nonce only once and remembers the 64 nonces
+    /// below the highest one it accepted from `sender` on the channel, older
+    /// ones are rejected.
+    pub nonce: u64,
+    /// The packet of the application, `IbcExecuteMsg` for the dispatcher.
+    pub msg: T,
+}
+
+/// Who triggered a packet on which chain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PacketSender {
+    pub chain_id: String,
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcExecuteMsg {
+    /// Answered with a pong. If `payload` is set its sha256 is returned, and
+    /// with `expect_echo` the payload itself too, to check it arrived intact.
+    Ping {
+        #[serde(default)]
+        payload: Option<Binary>,
+        #[serde(default)]
+        expect_echo: bool,
+    },
+    /// Answered with the number of pings the receiver has received on its
+    /// end of the channel, see `IbcSyncCounterResponse`.
+    SyncCounter {},
+    /// Routing envelope. A dispatcher receiving this relays it along the
+    /// first channel in `path`, or handles `inner` itself once `path` is
+    /// empty. `inner` has to be forwardable, see
+    /// `IbcExecuteMsg::is_forwardable`.
+    Forward {
+        /// Local channel ids of the remaining hops, one per intermediate
+        /// dispatcher.
+        path: Vec<String>,
+        /// Assigned by the sender of this hop and echoed back in
+        /// `ForwardAck`, so the sender can find where the packet came from.
+        id: u64,
+        inner: Box<IbcExecuteMsg>,
+    },
+    /// Final ack of a forwarded packet, sent back one hop towards the origin.
+    ForwardAck { id: u64, ack: Binary },
+    /// Executes `msg` on `contract` with the dispatcher as sender.
+    Execute { contract: String, msg: Binary },
+    /// Runs every message of `msgs`, only `Ping` and `Execute` are allowed.
+    /// If `atomic`, a failing message rolls back all of them, otherwise only
+    /// itself. The ack is a `BatchResponse`.
+    Batch {
+        msgs: Vec<IbcExecuteMsg>,
+        atomic: bool,
+    },
+    /// Administers the receiving dispatcher. Only accepted on a channel
+    /// whose counterparty port is the configured controller.
+    Admin(AdminMsg),
+}
+
+impl IbcExecuteMsg {
+    /// Whether the message can be the `inner` of a `Forward`. Routing
+    /// messages can not, and neither can `SyncCounter` and `Admin`: they are
+    /// about the channel they arrive on, which at the final hop is not a
+    /// channel of the origin.
+    pub fn is_forwardable(&self) -> bool {
+        matches!(
+            self,
+            IbcExecuteMsg::Ping { .. }
+                | IbcExecuteMsg::Execute { .. }
+                | IbcExecuteMsg::Batch { .. }
+        )
+    }
+}
+
+/// Admin commands, run by the local admin or sent by the controller.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminMsg {
+    /// Updates the fields that are set.
+    UpdateConfig {
+        admin: Option<String>,
+        controller_port: Option<String>,
+        degraded_after: Option<u32>,
+        unhealthy_after: Option<u32>,
+    },
+    /// Stops sending and receiving packets, except for admin packets and
+    /// the final acks of forwarded packets.
+    Pause {},
+    Unpause {},
+    /// Replaces the contracts `Execute` messages may call, `None` allows
+    /// none.
+    SetAllowedContracts {
+        contracts: Option<Vec<String>>,
+    },
+    /// Replaces the limits of received packets, `None` removes a limit.
+    SetLimits {
+        max_packet_bytes: Option<u32>,
+        max_execute_depth: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IbcPingResponse {
+    pub result: String,
+    /// The payload of the ping, if an echo was expected.
+    pub payload: Option<Binary>,
+    /// sha256 of the payload of the ping, if it had one.
+    pub sha256: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct IbcSyncCounterResponse {
+    /// Pings received on the channel the `SyncCounter` packet arrived on.
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BatchResponse {
+    /// One result per message of the batch, in the same order. A success
+    /// holds the ack data of the message, e.g. the `IbcPingResponse` of a
+    /// ping, a failure the redacted error.
+    pub results: Vec<Ack>,
+}
@@ -0,0 +1,200 @@
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, DepsMut, Env, IbcPacketReceiveMsg, IbcReceiveResponse,
+    MessageInfo, Reply, Response, Storage, SubMsg, SubMsgResult, WasmMsg,
+};
+use cw_utils::parse_execute_response_data;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ack::Ack,
+    dispatcher::Dispatcher,
+    error::Never,
+    events::DispatcherEvent,
+    handler::{HandlePacketMsg, PacketContext, PacketHandler},
+    ibc_msg::{DispatcherPacket, PacketSender},
+    state::{PENDING_PACKET, RECEIVE_NONCES},
+    ContractError,
+};
+
+/// Number of nonces below the highest received one that are still accepted.
+pub const NONCE_WINDOW: u64 = 64;
+
+/// Reply id of the submessage handling a received packet.
+pub const RECEIVE_REPLY_ID: u64 = 1;
+
+/// The message a contract sends itself to handle a received packet. Its
+/// `ExecuteMsg` needs a matching `HandlePacket(HandlePacketMsg<_>)` variant.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HandleExecuteMsg<T> {
+    HandlePacket(HandlePacketMsg<T>),
+}
+
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    packet_receive(&Dispatcher, deps, env, msg)
+}
+
+pub fn packet_receive<H: PacketHandler>(
+    handler: &H,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    // Regardless of if our processing of this packet works we need to
+    // commit an ACK to the chain. As such, we wrap all handling logic
+    // in a seprate function and on error write out an error ack.
+    match do_packet_receive(handler, deps, env, msg) {
+        Ok(response) => Ok(response),
+        Err(error) => Ok(IbcReceiveResponse::new()
+            .add_attribute("error", error.to_string())
+            .set_ack(error_ack(error.to_string()))),
+    }
+}
+
+/// Serializes an error ack. Encoding a string does not fail in practice, but
+/// receive must not abort, so a generic error ack is written if it does.
+fn error_ack(error: String) -> Binary {
+    Ack::fail(error).unwrap_or_else(|_| Binary::from(br#"{"error":"invalid error"}"#.as_slice()))
+}
+
+pub fn do_packet_receive<H: PacketHandler>(
+    handler: &H,
+    mut deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, H::Error> {
+    // The channel this packet is being relayed along on this chain.
+    let sequence = msg.packet.sequence;
+    let channel = msg.packet.dest.channel_id;
+    handler.check(deps.branch(), &channel, &msg.packet.data)?;
+    let DispatcherPacket { sender, nonce, msg }: DispatcherPacket<H::Packet> =
+        from_binary(&msg.packet.data)?;
+    // Checked out here, so a packet whose handler fails can not be replayed.
+    verify_nonce(deps.storage, &channel, &sender, nonce)?;
+    let event = DispatcherEvent::Receive {
+        channel: channel.clone(),
+        sender: sender.address.clone(),
+        sender_chain_id: sender.chain_id.clone(),
+        nonce,
+        sequence,
+    };
+
+    let ctx = PacketContext { channel, sender };
+    handler.validate(deps.as_ref(), &env, &ctx, &msg)?;
+
+    // The handler runs in a submessage, if it fails all its writes are
+    // reverted. The reply writes the ack in both cases. Channel and sender
+    // are passed in storage, so a `HandlePacket` that does not come from
+    // here has nothing to handle.
+    PENDING_PACKET.save(deps.storage, &ctx)?;
+    let handle = WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&HandleExecuteMsg::HandlePacket(HandlePacketMsg { msg }))?,
+        funds: vec![],
+    };
+    Ok(IbcReceiveResponse::new()
+        .add_submessage(SubMsg::reply_always(handle, RECEIVE_REPLY_ID))
+        .add_event(event.into())
+        .set_ack(Ack::success()?))
+}
+
+/// Runs the handler for a received packet, called by the contract itself
+/// from `ibc_packet_receive`. The data of the response is the ack.
+pub fn handle_packet<H: PacketHandler>(
+    handler: &H,
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: HandlePacketMsg<H::Packet>,
+) -> Result<Response, H::Error> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {}.into());
+    }
+    // Every received packet is handled once.
+    let ctx = PENDING_PACKET
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    PENDING_PACKET.remove(deps.storage);
+
+    let res = handler.receive(deps, env, ctx, msg.msg)?;
+    // The handler only provides the result data, wrap it into a success ack.
+    let ack = if res.acknowledgement.is_empty() {
+        Ack::success()?
+    } else {
+        to_binary(&Ack::Result(res.acknowledgement))?
+    };
+
+    Ok(Response::new()
+        .add_submessages(res.messages)
+        .add_attributes(res.attributes)
+        .add_events(res.events)
+        .set_data(ack))
+}
+
+/// Handles the reply of `HandlePacket`. Data set here replaces the ack
+/// written by `ibc_packet_receive`. The error of a failed handler is
+/// redacted by the chain to its code, e.g. `codespace: wasm, code: 5`.
+pub fn packet_reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+    // Left over if the handler has failed.
+    PENDING_PACKET.remove(deps.storage);
+    match reply.result {
+        SubMsgResult::Ok(res) => {
+            let ack = match res.data {
+                Some(data) => parse_execute_response_data(&data)?.data,
+                None => None,
+            };
+            Ok(Response::new().set_data(match ack {
+                Some(ack) => ack,
+                None => Ack::success()?,
+            }))
+        }
+        SubMsgResult::Err(error) => Ok(Response::new()
+            .add_attribute("error", &error)
+            .set_data(error_ack(error))),
+    }
+}
+
+/// Rejects replayed packets. Channels are unordered, so every nonce within
+/// `NONCE_WINDOW` of the highest one of a sender on a channel is accepted
+/// once, in any order.
+fn verify_nonce(
+    storage: &mut dyn Storage,
+    channel: &str,
+    sender: &PacketSender,
+    nonce: u64,
+) -> Result<(), ContractError> {
+    let key = (channel, sender.address.as_str());
+    let mut window = RECEIVE_NONCES.may_load(storage, key)?.unwrap_or_default();
+    if nonce > window.highest {
+        let shift = nonce - window.highest;
+        window.received = if shift < NONCE_WINDOW {
+            window.received << shift | 1
+        } else {
+            1
+        };
+        window.highest = nonce;
+    } else {
+        let offset = window.highest - nonce;
+        // Nonces start at 1, 0 is never accepted.
+        if offset >= NONCE_WINDOW || nonce == 0 {
+            return Err(ContractError::StaleNonce {
+                sender: sender.address.clone(),
+                nonce,
+                oldest: window.highest.saturating_sub(NONCE_WINDOW),
+            });
+        }
+        if window.received & (1 << offset) != 0 {
+            return Err(ContractError::InvalidNonce {
+                sender: sender.address.clone(),
+                nonce,
+            });
+        }
+        window.received |= 1 << offset;
+    }
+    RECEIVE_NONCES.save(storage, key, &window)?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+pub mod ack;
+pub mod admin;
+pub mod contract;
+pub mod dispatcher;
+mod error;
+pub mod events;
+pub mod handler;
+pub mod health;
+pub mod ibc;
+pub mod ibc_ack;
+pub mod ibc_batch;
+pub mod ibc_forward;
+pub mod ibc_msg;
+pub mod ibc_receive;
+pub mod limits;
+pub mod msg;
+pub mod queue;
+pub mod state;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+#[cfg(test)]
+mod tests;
+
+pub use crate::error::{ContractError, Never};
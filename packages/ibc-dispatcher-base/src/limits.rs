@@ -0,0 +1,92 @@
+use cosmwasm_std::{from_slice, Deps, DepsMut, StdResult, Storage};
+
+use crate::{
+    admin::ensure_contract_allowed,
+    ibc_msg::{DispatcherPacket, IbcExecuteMsg},
+    state::{CONFIG, REJECTED_PACKETS},
+    ContractError,
+};
+
+/// Checks a received packet against the configured limits before it is
+/// handled, its size before it is even decoded. Rejected packets are counted
+/// per channel.
+pub fn check_packet(deps: DepsMut, channel: &str, data: &[u8]) -> Result<(), ContractError> {
+    let res = check_limits(deps.as_ref(), data);
+    if let Err(err) = &res {
+        count_rejected(deps.storage, channel, err)?;
+    }
+    res
+}
+
+fn check_limits(deps: Deps, data: &[u8]) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if let Some(max) = config.max_packet_bytes {
+        if data.len() > max as usize {
+            return Err(ContractError::PacketTooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+    }
+
+    let packet: DispatcherPacket<IbcExecuteMsg> = from_slice(data)?;
+    let msgs = match packet.msg {
+        IbcExecuteMsg::Batch { msgs, .. } => msgs,
+        msg => vec![msg],
+    };
+    for msg in msgs {
+        if let IbcExecuteMsg::Execute { contract, msg } = msg {
+            if let Some(max) = config.max_execute_depth {
+                let depth = json_depth(&msg);
+                if depth > max as usize {
+                    return Err(ContractError::ExecuteTooDeep { depth, max });
+                }
+            }
+            let contract = deps.api.addr_validate(&contract)?;
+            ensure_contract_allowed(deps.storage, &contract)?;
+        }
+    }
+    Ok(())
+}
+
+/// Counts `err` against `channel` if it is a rejection by the limits.
+fn count_rejected(storage: &mut dyn Storage, channel: &str, err: &ContractError) -> StdResult<()> {
+    let mut rejected = REJECTED_PACKETS
+        .may_load(storage, channel)?
+        .unwrap_or_default();
+    match err {
+        ContractError::PacketTooLarge { .. } => rejected.packet_too_large += 1,
+        ContractError::ExecuteTooDeep { .. } => rejected.execute_too_deep += 1,
+        ContractError::ContractNotAllowed { .. } => rejected.contract_not_allowed += 1,
+        _ => return Ok(()),
+    }
+    REJECTED_PACKETS.save(storage, channel, &rejected)
+}
+
+/// Deepest nesting of objects and arrays in a JSON document. Bytes in strings
+/// are skipped, the document is not validated otherwise.
+fn json_depth(json: &[u8]) -> usize {
+    let (mut depth, mut max) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
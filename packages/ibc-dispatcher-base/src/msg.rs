@@ -0,0 +1,222 @@
+use cosmwasm_std::{Binary, Timestamp};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ack::Ack,
+    handler::HandlePacketMsg,
+    ibc_msg::{AdminMsg, DispatcherPacket, IbcExecuteMsg},
+    state::{ChannelState, HealthStatus},
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// Allowed to run admin commands. Defaults to the instantiating account.
+    pub admin: Option<String>,
+    /// Consecutive timeouts and error acks after which a channel is
+    /// degraded. Defaults to 3.
+    pub degraded_after: Option<u32>,
+    /// Consecutive timeouts and error acks after which a channel is
+    /// unhealthy. Defaults to 10.
+    pub unhealthy_after: Option<u32>,
+    /// Counterparty port of the contract allowed to send admin packets,
+    /// e.g. `wasm.<address of the home dispatcher>`. None if not set.
+    pub controller_port: Option<String>,
+    /// Received packets larger than this are rejected. No limit if not set.
+    pub max_packet_bytes: Option<u32>,
+    /// Received `Execute` messages whose `msg` is nested deeper are
+    /// rejected. No limit if not set.
+    pub max_execute_depth: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    Ping {
+        channel: String,
+        /// Sent along and checked against the hash in the pong.
+        payload: Option<Binary>,
+        /// Asks the receiver to send the payload back.
+        expect_echo: Option<bool>,
+    },
+    /// Asks the counterparty how many pings it has received on `channel`,
+    /// see the `GetCounterSync` query.
+    SyncCounter {
+        channel: String,
+        /// Sends even if the channel is unhealthy.
+        force: Option<bool>,
+    },
+    /// Sends `inner` along `path`. The first channel is a local one, every
+    /// further channel is a channel of the dispatcher at the previous hop.
+    /// `inner` can be a `Ping`, an `Execute` or a `Batch`.
+    Forward {
+        path: Vec<String>,
+        inner: IbcExecuteMsg,
+        /// Sends even if the first channel is unhealthy.
+        force: Option<bool>,
+    },
+    /// Admin only. Drops packets queued for `channel` before `queued_before`.
+    DropQueued {
+        channel: String,
+        queued_before: Timestamp,
+    },
+    /// Sends `msgs` in one `Batch` packet along `channel`.
+    Batch {
+        channel: String,
+        msgs: Vec<IbcExecuteMsg>,
+        atomic: bool,
+        /// Sends even if the channel is unhealthy.
+        force: Option<bool>,
+    },
+    /// Admin only. Runs an admin command on this dispatcher.
+    Admin(AdminMsg),
+    /// Admin only. Sends an admin command to the dispatcher at the other end
+    /// of `channel`, which has to have this contract as controller.
+    SendAdmin {
+        channel: String,
+        msg: AdminMsg,
+        /// Sends even if the channel is unhealthy.
+        force: Option<bool>,
+    },
+    /// Handles a received packet. Only the dispatcher itself can call this,
+    /// from `ibc_packet_receive`.
+    HandlePacket(HandlePacketMsg<IbcExecuteMsg>),
+    /// Runs messages of a received batch, `first` is the position of the
+    /// first of `msgs` in the batch. Only the dispatcher itself can call
+    /// this, from its receive handler.
+    HandleBatch {
+        first: u32,
+        msgs: Vec<IbcExecuteMsg>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Channels ordered by id. Closed channels are only listed with
+    /// `include_closed`.
+    GetConnections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        include_closed: Option<bool>,
+    },
+    // GetCount returns the current count as a json-encoded number
+    GetCounter {
+        channel: String,
+    },
+    /// Pongs counted here next to the pings the counterparty has received,
+    /// as of the last `SyncCounter`.
+    GetCounterSync {
+        channel: String,
+    },
+    /// Failure counts and status of `channel`.
+    GetChannelHealth {
+        channel: String,
+    },
+    /// Received packets on `channel` rejected by the limits.
+    GetRejectedPackets {
+        channel: String,
+    },
+    /// Pong counts of all channels, ordered by channel id.
+    ListCounters {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Packets waiting for `channel` to complete its handshake.
+    GetQueue {
+        channel: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Final acks of forwarded packets that could not be sent back along
+    /// `channel`, ordered by forward id.
+    GetUndeliveredAcks {
+        channel: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GetConnectionsResponse {
+    pub connections: Vec<ConnectionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConnectionResponse {
+    pub channel: String,
+    pub state: ChannelState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GetCounterResponse {
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CounterSyncResponse {
+    pub channel: String,
+    /// Pongs received for pings sent on `channel`, as `GetCounter`.
+    pub local_count: u32,
+    /// Pings the counterparty has received, None until synced.
+    pub remote_count: Option<u32>,
+    /// `remote_count - local_count`. Positive if pings were received whose
+    /// pong did not arrive (yet), negative if pongs were counted twice.
+    pub difference: Option<i64>,
+    pub synced_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ChannelHealthResponse {
+    pub channel: String,
+    pub status: HealthStatus,
+    pub consecutive_timeouts: u32,
+    pub consecutive_errors: u32,
+    pub corruptions: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RejectedPacketsResponse {
+    pub channel: String,
+    pub packet_too_large: u32,
+    pub execute_too_deep: u32,
+    pub contract_not_allowed: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ListCountersResponse {
+    pub counters: Vec<CounterResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CounterResponse {
+    pub channel: String,
+    pub count: u32,
+    /// Counters of closed channels are kept, this tells them apart.
+    pub state: ChannelState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GetQueueResponse {
+    pub packets: Vec<QueuedPacketResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GetUndeliveredAcksResponse {
+    pub acks: Vec<UndeliveredAckResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct UndeliveredAckResponse {
+    /// The id the previous hop assigned to the forwarded packet.
+    pub id: u64,
+    pub ack: Ack,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct QueuedPacketResponse {
+    pub id: u64,
+    pub packet: DispatcherPacket<IbcExecuteMsg>,
+    pub queued_at: Timestamp,
+}
@@ -0,0 +1,90 @@
+use cosmwasm_std::{Binary, Env, IbcMsg, IbcTimeout, Order, StdResult, Storage, Timestamp};
+use cw_storage_plus::Bound;
+
+use crate::{
+    ibc::PACKET_LIFETIME,
+    state::{QueuedPacket, QUEUE, QUEUE_ID},
+};
+
+/// Stores a packet until its channel is open. Returns the queue id.
+pub fn enqueue(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    data: Binary,
+) -> StdResult<u64> {
+    let id = QUEUE_ID.may_load(storage)?.unwrap_or_default() + 1;
+    QUEUE_ID.save(storage, &id)?;
+    QUEUE.save(
+        storage,
+        (channel, id),
+        &QueuedPacket {
+            data,
+            queued_at: env.block.time,
+        },
+    )?;
+    Ok(id)
+}
+
+/// Removes all queued packets of `channel` and creates the messages sending
+/// them, in the order they were queued. The timeout starts now, not when the
+/// packet was queued.
+pub fn flush(storage: &mut dyn Storage, env: &Env, channel: &str) -> StdResult<Vec<IbcMsg>> {
+    let queued = QUEUE
+        .prefix(channel)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    queued
+        .into_iter()
+        .map(|(id, queued)| {
+            QUEUE.remove(storage, (channel, id));
+            Ok(IbcMsg::SendPacket {
+                channel_id: channel.to_string(),
+                data: queued.data,
+                timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(PACKET_LIFETIME)),
+            })
+        })
+        .collect()
+}
+
+/// Removes packets of `channel` queued before `queued_before`. Returns the
+/// number of dropped packets.
+pub fn drop_stale(
+    storage: &mut dyn Storage,
+    channel: &str,
+    queued_before: Timestamp,
+) -> StdResult<u32> {
+    let stale = QUEUE
+        .prefix(channel)
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, queued)) => queued.queued_at < queued_before,
+            Err(_) => true,
+        })
+        .map(|item| item.map(|(id, _)| id))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for id in &stale {
+        QUEUE.remove(storage, (channel, *id));
+    }
+    Ok(stale.len() as u32)
+}
+
+pub fn queued_packets(
+    storage: &dyn Storage,
+    channel: &str,
+    start_after: Option<u64>,
+    limit: usize,
+) -> StdResult<Vec<(u64, QueuedPacket)>> {
+    QUEUE
+        .prefix(channel)
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect()
+}
@@ -0,0 +1,178 @@
+use cosmwasm_std::{Addr, Binary, Timestamp};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ack::Ack,
+    handler::PacketContext,
+    ibc_msg::{IbcExecuteMsg, PacketSender},
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub admin: Addr,
+    /// Consecutive failed packets after which a channel is degraded.
+    pub degraded_after: u32,
+    /// Consecutive failed packets after which a channel is unhealthy.
+    pub unhealthy_after: u32,
+    /// Counterparty port admin packets are accepted from.
+    pub controller_port: Option<String>,
+    pub paused: bool,
+    /// Contracts received `Execute` messages may call, none if `None`.
+    pub allowed_contracts: Option<Vec<Addr>>,
+    /// Received packets larger than this are rejected.
+    pub max_packet_bytes: Option<u32>,
+    /// Received `Execute` messages whose `msg` is nested deeper are rejected.
+    pub max_execute_depth: Option<u32>,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelState {
+    /// The handshake has started, packets are queued until it completes.
+    Opening,
+    Open,
+    /// Kept after the channel has been closed, nothing can be sent anymore.
+    Closed,
+}
+
+// Mapping between connections and the counter on that connection.
+pub const CONNECTIONS: Map<&str, ChannelState> = Map::new("connections");
+// The channels of `CONNECTIONS` that are not closed, so listing them does
+// not have to skip closed ones.
+pub const ACTIVE_CONNECTIONS: Map<&str, ChannelState> = Map::new("active_connections");
+pub const COUNTERS: Map<&str, u32> = Map::new("counters");
+// Pings received per channel, reported to the counterparty on `SyncCounter`.
+pub const RECEIVED_PINGS: Map<&str, u32> = Map::new("received_pings");
+// Pings the counterparty has received on each channel, as of its last
+// `SyncCounter` answer.
+pub const REMOTE_COUNTERS: Map<&str, RemoteCounter> = Map::new("remote_counters");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RemoteCounter {
+    pub count: u32,
+    /// Block time the answer has been received at.
+    pub synced_at: Timestamp,
+}
+// Received packets rejected by the limits of the config, per channel.
+pub const REJECTED_PACKETS: Map<&str, RejectedPackets> = Map::new("rejected_packets");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct RejectedPackets {
+    pub packet_too_large: u32,
+    pub execute_too_deep: u32,
+    pub contract_not_allowed: u32,
+}
+
+// Port of the contract at the other end of each channel.
+pub const COUNTERPARTY_PORTS: Map<&str, String> = Map::new("counterparty_ports");
+
+// Packets for channels that are not open yet, keyed by channel and queue id.
+pub const QUEUE: Map<(&str, u64), QueuedPacket> = Map::new("queue");
+pub const QUEUE_ID: Item<u64> = Item::new("queue_id");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct QueuedPacket {
+    /// The encoded `DispatcherPacket`.
+    pub data: Binary,
+    pub queued_at: Timestamp,
+}
+
+// Last nonce used per channel and sender for outgoing packets, and the
+// nonces accepted per channel and sender for incoming packets.
+pub const SEND_NONCES: Map<(&str, &str), u64> = Map::new("channel_send_nonces");
+pub const RECEIVE_NONCES: Map<(&str, &str), NonceWindow> = Map::new("receive_nonce_windows");
+
+/// Nonces accepted from a sender. Bit `i` of `received` is set if nonce
+/// `highest - i` has been accepted, older nonces are rejected.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct NonceWindow {
+    pub highest: u64,
+    pub received: u64,
+}
+
+// The packet `ibc_packet_receive` is handling, until its `HandlePacket`
+// submessage runs. Only set within the receiving transaction.
+pub const PENDING_PACKET: Item<PacketContext> = Item::new("pending_packet");
+
+// Forwarded packets waiting for their final ack, keyed by the channel the
+// packet was sent on and the id put into its envelope.
+pub const FORWARDS: Map<(&str, u64), ForwardOrigin> = Map::new("forwards");
+pub const FORWARD_ID: Item<u64> = Item::new("forward_id");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardOrigin {
+    /// The packet was sent by this contract, the final ack is handled here.
+    Local { inner: IbcExecuteMsg },
+    /// The packet was relayed for a previous hop, the final ack is sent back
+    /// along `channel` with the id that hop used.
+    Hop { channel: String, id: u64 },
+}
+
+// Times the final ack sent back along a channel with a forward id has been
+// resent after timing out.
+pub const FORWARD_ACK_RETRIES: Map<(&str, u64), u32> = Map::new("forward_ack_retries");
+// Final acks that could not be sent back along a channel, by forward id.
+// Kept so the admin can see which forwards the origin never learns about.
+pub const UNDELIVERED_ACKS: Map<(&str, u64), Ack> = Map::new("undelivered_acks");
+
+// The batch being received, `HandleBatch` runs its messages with the
+// channel and sender stored here and records their results. Replies
+// record failures and write the results as ack. Removed once the packet
+// is handled.
+pub const PENDING_BATCH: Item<PendingBatch> = Item::new("pending_batch");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingBatch {
+    /// Channel and sender of the packet holding the batch.
+    pub channel: String,
+    pub sender: PacketSender,
+    pub atomic: bool,
+    pub results: Vec<Ack>,
+}
+
+// Health of the channels packets have been sent on.
+pub const CHANNEL_HEALTH: Map<&str, ChannelHealth> = Map::new("channel_health");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct ChannelHealth {
+    pub status: HealthStatus,
+    /// Timeouts since the last successful ack.
+    pub consecutive_timeouts: u32,
+    /// Error acks since the last successful ack.
+    pub consecutive_errors: u32,
+    /// Pongs that could not be decoded or whose payload hash did not
+    /// match, in total.
+    pub corruptions: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    /// Nothing but pings is sent unless forced.
+    Unhealthy,
+}
+
+// Deriving it with `#[default]` needs Rust 1.62, CI builds with 1.58.
+#[allow(clippy::derivable_impls)]
+impl Default for HealthStatus {
+    fn default() -> Self {
+        HealthStatus::Healthy
+    }
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Unhealthy => "unhealthy",
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use cosmwasm_std::{
+    from_binary, Binary, CosmosMsg, DepsMut, Env, IbcPacketReceiveMsg, IbcReceiveResponse,
+    MessageInfo, Reply, SubMsgResponse, SubMsgResult, WasmMsg,
+};
+
+use crate::{
+    handler::PacketHandler,
+    ibc_receive::{
+        handle_packet, packet_receive, packet_reply, HandleExecuteMsg, RECEIVE_REPLY_ID,
+    },
+};
+
+/// Receives a packet the way the chain does: runs `ibc_packet_receive`, the
+/// `HandlePacket` submessage and its reply. Returns the messages and
+/// attributes of the handler together with the final ack. Unlike on chain,
+/// writes of a failing handler are not reverted.
+pub fn receive_packet<H: PacketHandler>(
+    handler: &H,
+    mut deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> IbcReceiveResponse {
+    let res = packet_receive(handler, deps.branch(), env.clone(), msg).unwrap();
+    let handle = match res.messages.first().map(|msg| (msg.id, &msg.msg)) {
+        Some((RECEIVE_REPLY_ID, CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }))) => msg,
+        // Rejected before the handler runs, the error ack is written already.
+        _ => return res,
+    };
+    let HandleExecuteMsg::HandlePacket(msg) = from_binary(handle).unwrap();
+
+    let info = MessageInfo {
+        sender: env.contract.address.clone(),
+        funds: vec![],
+    };
+    let mut received = IbcReceiveResponse::new().add_events(res.events.clone());
+    let result = match handle_packet(handler, deps.branch(), env.clone(), info, msg) {
+        Ok(res) => {
+            received = received
+                .add_submessages(res.messages)
+                .add_attributes(res.attributes)
+                .add_events(res.events);
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: res.data.map(execute_response_data),
+            })
+        }
+        Err(err) => SubMsgResult::Err(err.to_string()),
+    };
+
+    let reply = Reply {
+        id: RECEIVE_REPLY_ID,
+        result,
+    };
+    let res = packet_reply(deps, env, reply).unwrap();
+    received
+        .add_attributes(res.attributes)
+        .set_ack(res.data.unwrap())
+}
+
+/// Encodes `data` as `MsgExecuteContractResponse`, the way the chain passes
+/// it to the reply of an execute submessage.
+pub fn execute_response_data(data: Binary) -> Binary {
+    // Field 1, length delimited.
+    let mut encoded = vec![0x0a];
+    let mut len = data.len();
+    while len >= 0x80 {
+        encoded.push((len as u8) | 0x80);
+        len >>= 7;
+    }
+    encoded.push(len as u8);
+    encoded.extend_from_slice(&data);
+    Binary::from(encoded)
+}
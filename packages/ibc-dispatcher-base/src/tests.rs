@@ -0,0 +1,1911 @@
+//refrence: https://mirror.cnhub.dev/confio/cw-ibc-demo/blob/main/contracts/simple-ica-host/src/contract.rs
+
+use crate::ack::Ack;
+use crate::contract::{execute, instantiate, query, reply};
+use crate::dispatcher::Dispatcher;
+use crate::events::DispatcherEvent;
+use crate::handler::HandlePacketMsg;
+use crate::ibc::{
+    ibc_channel_close, ibc_channel_connect, ibc_channel_open, ibc_packet_timeout, IBC_ORDER,
+    IBC_VERSION,
+};
+use crate::ibc_ack::ibc_packet_ack;
+use crate::ibc_batch::BATCH_REPLY_ID;
+use crate::ibc_forward::MAX_FORWARD_ACK_RETRIES;
+use crate::ibc_msg::{
+    AdminMsg, BatchResponse, DispatcherPacket, IbcPingResponse, IbcSyncCounterResponse,
+    PacketSender,
+};
+use crate::msg::GetCounterResponse;
+use crate::testing::{execute_response_data, receive_packet};
+use crate::{
+    ibc_msg::IbcExecuteMsg,
+    ibc_receive::{ibc_packet_receive, RECEIVE_REPLY_ID},
+    msg::{
+        ChannelHealthResponse, ConnectionResponse, CounterResponse, CounterSyncResponse,
+        ExecuteMsg, GetConnectionsResponse, GetQueueResponse, GetUndeliveredAcksResponse,
+        InstantiateMsg, ListCountersResponse, QueryMsg, RejectedPacketsResponse,
+        UndeliveredAckResponse,
+    },
+    state::{ChannelState, HealthStatus, CONFIG, COUNTERS, FORWARDS, RECEIVED_PINGS},
+    ContractError,
+};
+
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack,
+    mock_ibc_channel_open_init, mock_ibc_channel_open_try, mock_ibc_packet_ack,
+    mock_ibc_packet_recv, mock_ibc_packet_timeout, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    from_binary, to_binary, to_vec, Attribute, Binary, CosmosMsg, Deps, DepsMut, Event,
+    IbcAcknowledgement, IbcMsg, IbcOrder, IbcReceiveResponse, IbcTimeout, MessageInfo, OwnedDeps,
+    Reply, ReplyOn, Response, Storage, SubMsgResponse, SubMsgResult, WasmMsg,
+};
+
+const CREATER_ADDR: &str = "creater";
+const TEST_CHANNEL: &str = "channel-1";
+const REMOTE_SENDER: &str = "remote-sender";
+
+pub const BAD_IBC_ORDER: IbcOrder = IbcOrder::Ordered;
+
+//Quick init of the contract
+fn setup(
+    info: Option<MessageInfo>,
+    msg: Option<InstantiateMsg>,
+) -> (
+    OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    Response,
+    MessageInfo,
+) {
+    let mut deps = mock_dependencies();
+
+    let info = match info {
+        Some(info) => info,
+        None => mock_info(CREATER_ADDR, &[]),
+    };
+
+    let msg = match msg {
+        Some(msg) => msg,
+        None => InstantiateMsg {
+            admin: None,
+            degraded_after: None,
+            unhealthy_after: None,
+            controller_port: None,
+            max_packet_bytes: None,
+            max_execute_depth: None,
+        },
+    };
+
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    (deps, res, info)
+}
+
+//If you do anything on init for the IBC to work, doesn't require to call this function if your contract doesn't rely on IBC connection to happen.
+fn connect(mut deps: DepsMut, channel_id: &str) {
+    let handshake_open = mock_ibc_channel_open_init(channel_id, IBC_ORDER, IBC_VERSION);
+    // first we try to open with a valid handshake
+    ibc_channel_open(deps.branch(), mock_env(), handshake_open).unwrap();
+
+    // then we connect (with counter-party version set)
+    let handshake_connect = mock_ibc_channel_connect_ack(channel_id, IBC_ORDER, IBC_VERSION);
+    let res = ibc_channel_connect(deps.branch(), mock_env(), handshake_connect).unwrap();
+
+    assert_eq!(0, res.messages.len());
+}
+
+//Wraps a message the way a remote dispatcher would.
+fn envelope(nonce: u64, msg: IbcExecuteMsg) -> DispatcherPacket<IbcExecuteMsg> {
+    DispatcherPacket {
+        sender: PacketSender {
+            chain_id: "remote-chain".to_string(),
+            address: REMOTE_SENDER.to_string(),
+        },
+        nonce,
+        msg,
+    }
+}
+
+fn ping() -> IbcExecuteMsg {
+    IbcExecuteMsg::Ping {
+        payload: None,
+        expect_echo: false,
+    }
+}
+
+//Returns the channel and the packet of a `SendPacket` message.
+fn sent_packet(msg: &CosmosMsg) -> (String, DispatcherPacket<IbcExecuteMsg>) {
+    match msg {
+        CosmosMsg::Ibc(IbcMsg::SendPacket {
+            channel_id, data, ..
+        }) => (channel_id.clone(), from_binary(data).unwrap()),
+        msg => panic!("not a packet: {:?}", msg),
+    }
+}
+
+fn query_count(deps: Deps, channel: &str) -> u32 {
+    let msg = QueryMsg::GetCounter {
+        channel: channel.to_string(),
+    };
+    let res: GetCounterResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.count
+}
+
+fn query_connections(deps: Deps, include_closed: bool) -> Vec<ConnectionResponse> {
+    let msg = QueryMsg::GetConnections {
+        start_after: None,
+        limit: None,
+        include_closed: Some(include_closed),
+    };
+    let res: GetConnectionsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.connections
+}
+
+#[test]
+fn proper_initialization() {
+    let (mut _deps, res, _info) = setup(None, None);
+
+    assert_eq!(0, res.messages.len());
+    assert!(res.attributes.is_empty());
+}
+
+#[test]
+fn enforce_version_in_handshake() {
+    let (mut deps, _res, _info) = setup(None, None);
+
+    let wrong_order = mock_ibc_channel_open_try(TEST_CHANNEL, BAD_IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), wrong_order).unwrap_err();
+
+    let wrong_version = mock_ibc_channel_open_try(TEST_CHANNEL, IBC_ORDER, "wrong_version");
+    ibc_channel_open(deps.as_mut(), mock_env(), wrong_version).unwrap_err();
+
+    let valid_handshake = mock_ibc_channel_open_try(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), valid_handshake).unwrap();
+}
+
+#[test]
+fn proper_handshake_flow() {
+    let (mut deps, _res, _info) = setup(None, None);
+
+    let handshake_open = mock_ibc_channel_open_init(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), handshake_open).unwrap();
+
+    // then we connect (with counter-party version set)
+    let handshake_connect = mock_ibc_channel_connect_ack(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    let res = ibc_channel_connect(deps.as_mut(), mock_env(), handshake_connect).unwrap();
+
+    assert_eq!(res.messages, vec![]);
+    assert!(res.attributes.is_empty());
+    assert_eq!(res.events[0].ty, "dispatcher_open");
+
+    //We should have 1 item (test_channel) in the connections.
+    let connections = query_connections(deps.as_ref(), false);
+
+    assert_eq!(1, connections.len());
+    assert_eq!(TEST_CHANNEL, connections[0].channel);
+    assert_eq!(ChannelState::Open, connections[0].state);
+}
+
+#[test]
+fn execute_ping() {
+    let (mut deps, _res, info) = setup(None, None);
+
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    //verify attributes are correct.
+    assert_eq!(
+        res.attributes,
+        vec![
+            Attribute {
+                key: "channel".to_string(),
+                value: TEST_CHANNEL.to_string()
+            },
+            Attribute {
+                key: "queued".to_string(),
+                value: "false".to_string()
+            },
+        ]
+    );
+
+    //Verify sent IbcPacket is correct
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Ibc(IbcMsg::SendPacket {
+            channel_id: TEST_CHANNEL.to_string(),
+            data: to_binary(&DispatcherPacket {
+                sender: PacketSender {
+                    chain_id: mock_env().block.chain_id,
+                    address: CREATER_ADDR.to_string(),
+                },
+                nonce: 1,
+                msg: ping(),
+            })
+            .unwrap(),
+            timeout: IbcTimeout::with_timestamp(mock_env().block.time.plus_seconds(300))
+        })
+    );
+
+    // Verify we received the ping, and answered correctly.
+    let ibc_msg = envelope(1, ping());
+
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &ibc_msg).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+
+    //Verify we got the result attr, pong, and the remote sender.
+    assert_eq!(
+        res.attributes,
+        vec![
+            Attribute {
+                key: "sender".to_string(),
+                value: REMOTE_SENDER.to_string()
+            },
+            Attribute {
+                key: "sender_chain_id".to_string(),
+                value: "remote-chain".to_string()
+            },
+        ]
+    );
+
+    let ack: IbcPingResponse = Ack::parse(res.acknowledgement.clone());
+
+    assert_eq!(ack.result.as_str(), "pong");
+
+    //Verify we do the ack correctly.
+    let ack = mock_ibc_packet_ack(
+        TEST_CHANNEL,
+        &ibc_msg,
+        IbcAcknowledgement::new(res.acknowledgement),
+    )
+    .unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+
+    assert!(res.attributes.is_empty());
+
+    //Verify that after the ack, our counter is 1 and not 0
+    let msg = QueryMsg::GetCounter {
+        channel: TEST_CHANNEL.to_string(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: GetCounterResponse = from_binary(&res).unwrap();
+
+    assert_eq!(value.count, 1);
+
+    //Do custom query
+    assert_eq!(
+        query_connections(deps.as_ref(), false),
+        vec![ConnectionResponse {
+            channel: TEST_CHANNEL.to_string(),
+            state: ChannelState::Open
+        }]
+    );
+}
+
+#[test]
+fn close_channel() {
+    let (mut deps, _res, info) = setup(None, None);
+
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    assert_eq!(
+        query_connections(deps.as_ref(), false),
+        vec![ConnectionResponse {
+            channel: TEST_CHANNEL.to_string(),
+            state: ChannelState::Open
+        }]
+    );
+
+    let channel = mock_ibc_channel_close_init(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    let res = ibc_channel_close(deps.as_mut(), mock_env(), channel).unwrap();
+
+    //Check no messages
+    assert_eq!(0, res.messages.len());
+
+    //Verify attributes
+    assert!(res.attributes.is_empty());
+    assert_eq!(res.events[0].ty, "dispatcher_close");
+
+    // Closed channels are only listed on request.
+    assert!(query_connections(deps.as_ref(), false).is_empty());
+    assert_eq!(
+        query_connections(deps.as_ref(), true),
+        vec![ConnectionResponse {
+            channel: TEST_CHANNEL.to_string(),
+            state: ChannelState::Closed
+        }]
+    );
+
+    // Nothing can be sent on a closed channel.
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("channel ({}) is closed", TEST_CHANNEL)
+    );
+}
+
+#[test]
+fn paginate_connections_and_counters() {
+    let (mut deps, _res, _info) = setup(None, None);
+
+    for i in 0..25 {
+        connect(deps.as_mut(), &format!("channel-{:02}", i));
+    }
+    let channel = mock_ibc_channel_close_init("channel-00", IBC_ORDER, IBC_VERSION);
+    ibc_channel_close(deps.as_mut(), mock_env(), channel).unwrap();
+
+    // Pages of the default size, starting after the last channel seen.
+    let msg = QueryMsg::GetConnections {
+        start_after: None,
+        limit: None,
+        include_closed: None,
+    };
+    let page: GetConnectionsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(page.connections.len(), 10);
+    assert_eq!(page.connections[0].channel, "channel-01");
+
+    let msg = QueryMsg::GetConnections {
+        start_after: Some(page.connections[9].channel.clone()),
+        limit: Some(100),
+        include_closed: Some(true),
+    };
+    let page: GetConnectionsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(page.connections.len(), 14);
+    assert_eq!(page.connections[0].channel, "channel-11");
+
+    let msg = QueryMsg::ListCounters {
+        start_after: Some("channel-20".to_string()),
+        limit: Some(2),
+    };
+    let page: ListCountersResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        page.counters,
+        vec![
+            CounterResponse {
+                channel: "channel-21".to_string(),
+                count: 0,
+                state: ChannelState::Open,
+            },
+            CounterResponse {
+                channel: "channel-22".to_string(),
+                count: 0,
+                state: ChannelState::Open,
+            },
+        ]
+    );
+
+    // The counter of a closed channel is listed with its state.
+    let msg = QueryMsg::ListCounters {
+        start_after: None,
+        limit: Some(1),
+    };
+    let page: ListCountersResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(page.counters[0].state, ChannelState::Closed);
+
+    // Keys that are no valid channel id are reported instead of skipped.
+    let mut key = (b"connections".len() as u16).to_be_bytes().to_vec();
+    key.extend(b"connections");
+    key.push(0xff);
+    deps.storage
+        .set(&key, &to_vec(&ChannelState::Open).unwrap());
+    let msg = QueryMsg::GetConnections {
+        start_after: Some("channel-24".to_string()),
+        limit: None,
+        include_closed: Some(true),
+    };
+    query(deps.as_ref(), mock_env(), msg).unwrap_err();
+
+    // So are counters that can not be read.
+    deps.storage
+        .set(&COUNTERS.key("channel-01"), b"not a count");
+    let msg = QueryMsg::GetCounter {
+        channel: "channel-01".to_string(),
+    };
+    query(deps.as_ref(), mock_env(), msg).unwrap_err();
+}
+
+fn query_undelivered_acks(deps: Deps, channel: &str) -> Vec<UndeliveredAckResponse> {
+    let msg = QueryMsg::GetUndeliveredAcks {
+        channel: channel.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res: GetUndeliveredAcksResponse =
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.acks
+}
+
+/// Relays a forwarded ping through `deps` as the middle hop and returns the
+/// pong it sends back along `channel-ba`.
+fn forwarded_pong(mut deps: DepsMut) -> DispatcherPacket<IbcExecuteMsg> {
+    let (mut chain_c, _res, _info) = setup(None, None);
+    connect(chain_c.as_mut(), "channel-cb");
+    let packet_ab = envelope(
+        1,
+        IbcExecuteMsg::Forward {
+            id: 1,
+            path: vec!["channel-bc".to_string()],
+            inner: Box::new(ping()),
+        },
+    );
+
+    let msg = mock_ibc_packet_recv("channel-ba", &packet_ab).unwrap();
+    let res = receive_packet(&Dispatcher, deps.branch(), mock_env(), msg);
+    let (_, packet_bc) = sent_packet(&res.messages[0].msg);
+
+    let msg = mock_ibc_packet_recv("channel-cb", &packet_bc).unwrap();
+    let res = receive_packet(&Dispatcher, chain_c.as_mut(), mock_env(), msg);
+    let ack = mock_ibc_packet_ack(
+        "channel-bc",
+        &packet_bc,
+        IbcAcknowledgement::new(res.acknowledgement),
+    )
+    .unwrap();
+    let res = ibc_packet_ack(deps, mock_env(), ack).unwrap();
+    let (_, packet_ba) = sent_packet(&res.messages[0].msg);
+    packet_ba
+}
+
+#[test]
+fn forward_ping_over_two_hops() {
+    // A -> B -> C, every chain runs a dispatcher.
+    let (mut chain_a, _res, info) = setup(None, None);
+    let (mut chain_b, _res, _info) = setup(None, None);
+    let (mut chain_c, _res, _info) = setup(None, None);
+    connect(chain_a.as_mut(), "channel-ab");
+    connect(chain_b.as_mut(), "channel-ba");
+    connect(chain_b.as_mut(), "channel-bc");
+    connect(chain_c.as_mut(), "channel-cb");
+
+    let msg = ExecuteMsg::Forward {
+        path: vec!["channel-ab".to_string(), "channel-bc".to_string()],
+        inner: ping(),
+        force: None,
+    };
+    let res = execute(chain_a.as_mut(), mock_env(), info, msg).unwrap();
+    let (channel, packet_ab) = sent_packet(&res.messages[0].msg);
+    assert_eq!(channel, "channel-ab");
+    assert_eq!(
+        packet_ab.msg,
+        IbcExecuteMsg::Forward {
+            path: vec!["channel-bc".to_string()],
+            id: 1,
+            inner: Box::new(ping()),
+        }
+    );
+
+    // B relays the packet to C and acks the first hop.
+    let msg = mock_ibc_packet_recv("channel-ba", &packet_ab).unwrap();
+    let res = receive_packet(&Dispatcher, chain_b.as_mut(), mock_env(), msg);
+    let (channel, packet_bc) = sent_packet(&res.messages[0].msg);
+    assert_eq!(channel, "channel-bc");
+    assert_eq!(packet_bc.sender, packet_ab.sender);
+    assert_eq!(
+        packet_bc.msg,
+        IbcExecuteMsg::Forward {
+            path: vec![],
+            id: 1,
+            inner: Box::new(ping()),
+        }
+    );
+
+    // The hop ack does not count as a pong.
+    let ack = mock_ibc_packet_ack(
+        "channel-ab",
+        &packet_ab,
+        IbcAcknowledgement::new(res.acknowledgement),
+    )
+    .unwrap();
+    ibc_packet_ack(chain_a.as_mut(), mock_env(), ack).unwrap();
+    assert_eq!(query_count(chain_a.as_ref(), "channel-ab"), 0);
+
+    // C answers the ping and counts it like a directly received one.
+    let msg = mock_ibc_packet_recv("channel-cb", &packet_bc).unwrap();
+    let res = receive_packet(&Dispatcher, chain_c.as_mut(), mock_env(), msg);
+    let pong: IbcPingResponse = Ack::parse(res.acknowledgement.clone());
+    assert_eq!(pong.result, "pong");
+    assert_eq!(
+        RECEIVED_PINGS.load(chain_c.as_ref().storage, "channel-cb"),
+        Ok(1)
+    );
+
+    // B sends the pong back to A.
+    let ack = mock_ibc_packet_ack(
+        "channel-bc",
+        &packet_bc,
+        IbcAcknowledgement::new(res.acknowledgement),
+    )
+    .unwrap();
+    let res = ibc_packet_ack(chain_b.as_mut(), mock_env(), ack).unwrap();
+    let (channel, packet_ba) = sent_packet(&res.messages[0].msg);
+    assert_eq!(channel, "channel-ba");
+
+    let msg = mock_ibc_packet_recv("channel-ab", &packet_ba).unwrap();
+    receive_packet(&Dispatcher, chain_a.as_mut(), mock_env(), msg);
+    assert_eq!(query_count(chain_a.as_ref(), "channel-ab"), 1);
+}
+
+#[test]
+fn forward_timeout_at_hop() {
+    let (mut chain_a, _res, info) = setup(None, None);
+    let (mut chain_b, _res, _info) = setup(None, None);
+    connect(chain_a.as_mut(), "channel-ab");
+    connect(chain_b.as_mut(), "channel-ba");
+    connect(chain_b.as_mut(), "channel-bc");
+
+    let msg = ExecuteMsg::Forward {
+        path: vec!["channel-ab".to_string(), "channel-bc".to_string()],
+        inner: ping(),
+        force: None,
+    };
+    let res = execute(chain_a.as_mut(), mock_env(), info, msg).unwrap();
+    let (_, packet_ab) = sent_packet(&res.messages[0].msg);
+
+    let msg = mock_ibc_packet_recv("channel-ba", &packet_ab).unwrap();
+    let res = receive_packet(&Dispatcher, chain_b.as_mut(), mock_env(), msg);
+    let (_, packet_bc) = sent_packet(&res.messages[0].msg);
+
+    // The second hop times out, B reports an error ack back to A.
+    let msg = mock_ibc_packet_timeout("channel-bc", &packet_bc).unwrap();
+    let res = ibc_packet_timeout(chain_b.as_mut(), mock_env(), msg).unwrap();
+    let (channel, packet_ba) = sent_packet(&res.messages[0].msg);
+    assert_eq!(channel, "channel-ba");
+    match &packet_ba.msg {
+        IbcExecuteMsg::ForwardAck { id, ack } => {
+            assert_eq!(*id, 1);
+            let ack: Ack = from_binary(ack).unwrap();
+            assert_eq!(ack.unwrap_err(), "packet timed out on channel (channel-bc)");
+        }
+        packet => panic!("unexpected packet: {:?}", packet),
+    }
+
+    let msg = mock_ibc_packet_recv("channel-ab", &packet_ba).unwrap();
+    let res = receive_packet(&Dispatcher, chain_a.as_mut(), mock_env(), msg);
+    assert!(res.attributes.iter().any(|a| a.key == "error"));
+    assert_eq!(query_count(chain_a.as_ref(), "channel-ab"), 0);
+}
+
+#[test]
+fn forward_ack_timeout() {
+    let (mut chain_a, _res, info) = setup(None, None);
+    let (mut chain_b, _res, _info) = setup(None, None);
+    let (mut chain_c, _res, _info) = setup(None, None);
+    connect(chain_a.as_mut(), "channel-ab");
+    connect(chain_b.as_mut(), "channel-ba");
+    connect(chain_b.as_mut(), "channel-bc");
+    connect(chain_c.as_mut(), "channel-cb");
+
+    let msg = ExecuteMsg::Forward {
+        path: vec!["channel-ab".to_string(), "channel-bc".to_string()],
+        inner: ping(),
+        force: None,
+    };
+    let res = execute(chain_a.as_mut(), mock_env(), info, msg).unwrap();
+    let (_, packet_ab) = sent_packet(&res.messages[0].msg);
+
+    let msg = mock_ibc_packet_recv("channel-ba", &packet_ab).unwrap();
+    let res = receive_packet(&Dispatcher, chain_b.as_mut(), mock_env(), msg);
+    let (_, packet_bc) = sent_packet(&res.messages[0].msg);
+
+    let msg = mock_ibc_packet_recv("channel-cb", &packet_bc).unwrap();
+    let res = receive_packet(&Dispatcher, chain_c.as_mut(), mock_env(), msg);
+    let ack = mock_ibc_packet_ack(
+        "channel-bc",
+        &packet_bc,
+        IbcAcknowledgement::new(res.acknowledgement),
+    )
+    .unwrap();
+    let res = ibc_packet_ack(chain_b.as_mut(), mock_env(), ack).unwrap();
+    let (_, packet_ba) = sent_packet(&res.messages[0].msg);
+
+    // The final ack times out on its way back, B resends the pong.
+    let msg = mock_ibc_packet_timeout("channel-ba", &packet_ba).unwrap();
+    let res = ibc_packet_timeout(chain_b.as_mut(), mock_env(), msg).unwrap();
+    let (channel, resent) = sent_packet(&res.messages[0].msg);
+    assert_eq!(channel, "channel-ba");
+    assert_eq!(resent.msg, packet_ba.msg);
+    match &resent.msg {
+        IbcExecuteMsg::ForwardAck { id, ack } => {
+            assert_eq!(*id, 1);
+            let ack: Ack = from_binary(ack).unwrap();
+            let pong: IbcPingResponse = from_binary(&ack.unwrap()).unwrap();
+            assert_eq!(pong.result, "pong");
+        }
+        packet => panic!("unexpected packet: {:?}", packet),
+    }
+
+    // A resolves the forward with the pong.
+    assert!(FORWARDS.has(chain_a.as_ref().storage, ("channel-ab", 1)));
+    let msg = mock_ibc_packet_recv("channel-ab", &resent).unwrap();
+    let res = receive_packet(&Dispatcher, chain_a.as_mut(), mock_env(), msg);
+    assert!(!res.attributes.iter().any(|a| a.key == "error"));
+    assert!(!FORWARDS.has(chain_a.as_ref().storage, ("channel-ab", 1)));
+    assert_eq!(query_count(chain_a.as_ref(), "channel-ab"), 1);
+    assert!(query_undelivered_acks(chain_b.as_ref(), "channel-ba").is_empty());
+}
+
+#[test]
+fn forward_ack_timeout_gives_up() {
+    let (mut chain_b, _res, _info) = setup(None, None);
+    connect(chain_b.as_mut(), "channel-ba");
+    connect(chain_b.as_mut(), "channel-bc");
+    let packet_ba = forwarded_pong(chain_b.as_mut());
+
+    // The pong is resent until the retries are used up, then kept.
+    for _ in 0..MAX_FORWARD_ACK_RETRIES {
+        let msg = mock_ibc_packet_timeout("channel-ba", &packet_ba).unwrap();
+        let res = ibc_packet_timeout(chain_b.as_mut(), mock_env(), msg).unwrap();
+        let (_, resent) = sent_packet(&res.messages[0].msg);
+        assert_eq!(resent.msg, packet_ba.msg);
+    }
+    let msg = mock_ibc_packet_timeout("channel-ba", &packet_ba).unwrap();
+    let res = ibc_packet_timeout(chain_b.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.events[1..],
+        vec![Event::from(DispatcherEvent::UndeliveredAck {
+            channel: "channel-ba".to_string(),
+            id: 1,
+            attempts: MAX_FORWARD_ACK_RETRIES + 1,
+        })]
+    );
+
+    let acks = query_undelivered_acks(chain_b.as_ref(), "channel-ba");
+    assert_eq!(acks.len(), 1);
+    assert_eq!(acks[0].id, 1);
+    let pong: IbcPingResponse = from_binary(&acks[0].ack.clone().unwrap()).unwrap();
+    assert_eq!(pong.result, "pong");
+}
+
+#[test]
+fn forward_ack_timeout_on_closed_channel() {
+    let (mut chain_b, _res, _info) = setup(None, None);
+    connect(chain_b.as_mut(), "channel-ba");
+    connect(chain_b.as_mut(), "channel-bc");
+    let packet_ba = forwarded_pong(chain_b.as_mut());
+
+    let channel = mock_ibc_channel_close_init("channel-ba", IBC_ORDER, IBC_VERSION);
+    ibc_channel_close(chain_b.as_mut(), mock_env(), channel).unwrap();
+
+    // Nothing can be sent on a closed channel, the pong is kept right away.
+    let msg = mock_ibc_packet_timeout("channel-ba", &packet_ba).unwrap();
+    let res = ibc_packet_timeout(chain_b.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res
+        .events
+        .iter()
+        .any(|e| e.ty == "dispatcher_undelivered_ack"));
+    assert_eq!(
+        query_undelivered_acks(chain_b.as_ref(), "channel-ba").len(),
+        1
+    );
+}
+
+#[test]
+fn forward_to_unknown_channel() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let msg = ExecuteMsg::Forward {
+        path: vec!["channel-404".to_string()],
+        inner: ping(),
+        force: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "no connection on channel (channel-404)");
+
+    // An intermediate hop without the next channel answers with an error ack.
+    let packet = envelope(
+        1,
+        IbcExecuteMsg::Forward {
+            path: vec!["channel-404".to_string()],
+            id: 7,
+            inner: Box::new(ping()),
+        },
+    );
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+    assert_eq!(ack.unwrap_err(), "no connection on channel (channel-404)");
+}
+
+#[test]
+fn forward_execute() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    // Messages about the channel they arrive on can not be forwarded.
+    let msg = ExecuteMsg::Forward {
+        path: vec![TEST_CHANNEL.to_string()],
+        inner: IbcExecuteMsg::SyncCounter {},
+        force: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidForward {}));
+
+    // The final hop checks `inner` like a directly received packet.
+    let execute_msg = IbcExecuteMsg::Execute {
+        contract: "target".to_string(),
+        msg: Binary::from(br#"{"mint":{}}"#),
+    };
+    let forward = |nonce| {
+        envelope(
+            nonce,
+            IbcExecuteMsg::Forward {
+                path: vec![],
+                id: 1,
+                inner: Box::new(execute_msg.clone()),
+            },
+        )
+    };
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &forward(1)).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+    assert_eq!(
+        ack.unwrap_err(),
+        "contract_not_allowed: contract (target) is not allowed"
+    );
+
+    let msg = ExecuteMsg::Admin(AdminMsg::SetAllowedContracts {
+        contracts: Some(vec!["target".to_string()]),
+    });
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &forward(2)).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.messages[0].id, RECEIVE_REPLY_ID);
+}
+
+#[test]
+fn reject_replayed_packet() {
+    let (mut deps, _res, _info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let packet = envelope(5, ping());
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg.clone());
+    let ack: IbcPingResponse = Ack::parse(res.acknowledgement);
+    assert_eq!(ack.result, "pong");
+
+    // The same packet again gets an error ack.
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+    assert_eq!(
+        ack.unwrap_err(),
+        format!(
+            "replayed packet from ({}), nonce (5) was already received",
+            REMOTE_SENDER
+        )
+    );
+
+    // An older packet overtaken by the newer one is accepted once.
+    let older = mock_ibc_packet_recv(TEST_CHANNEL, &envelope(4, ping())).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), older.clone());
+    let ack: IbcPingResponse = Ack::parse(res.acknowledgement);
+    assert_eq!(ack.result, "pong");
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), older);
+    let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+    assert!(ack.unwrap_err().starts_with("replayed packet"));
+
+    // Packets that fell out of the window are rejected.
+    let newest = mock_ibc_packet_recv(TEST_CHANNEL, &envelope(70, ping())).unwrap();
+    receive_packet(&Dispatcher, deps.as_mut(), mock_env(), newest);
+    let stale = mock_ibc_packet_recv(TEST_CHANNEL, &envelope(3, ping())).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), stale);
+    let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+    assert_eq!(
+        ack.unwrap_err(),
+        format!(
+            "packet from ({}) too old, nonce (3) must be above (6)",
+            REMOTE_SENDER
+        )
+    );
+
+    // Nonces are tracked per channel.
+    let msg = mock_ibc_packet_recv("channel-2", &packet).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    let ack: IbcPingResponse = Ack::parse(res.acknowledgement);
+    assert_eq!(ack.result, "pong");
+}
+
+#[test]
+fn sender_nonces_increase() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    for expected in 1..=3 {
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        let (_, packet) = sent_packet(&res.messages[0].msg);
+        assert_eq!(packet.nonce, expected);
+        assert_eq!(packet.sender.address, CREATER_ADDR);
+    }
+
+    // Every sender has its own nonce.
+    let res = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), msg).unwrap();
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+    assert_eq!(packet.nonce, 1);
+
+    // And on every channel, like the window of the receiver.
+    connect(deps.as_mut(), "channel-2");
+    let msg = ExecuteMsg::Ping {
+        channel: "channel-2".to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+    assert_eq!(packet.nonce, 1);
+}
+
+fn query_queue(deps: Deps, channel: &str) -> GetQueueResponse {
+    let msg = QueryMsg::GetQueue {
+        channel: channel.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn ping_unknown_channel() {
+    let (mut deps, _res, info) = setup(None, None);
+
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "no connection on channel (channel-1)");
+}
+
+#[test]
+fn queue_until_connected() {
+    let (mut deps, _res, info) = setup(None, None);
+
+    let handshake_open = mock_ibc_channel_open_init(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), handshake_open).unwrap();
+
+    // The channel is known but not open, pings are queued.
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    for _ in 0..2 {
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        assert!(res
+            .attributes
+            .contains(&Attribute::new("queued", "true".to_string())));
+    }
+
+    let queue = query_queue(deps.as_ref(), TEST_CHANNEL);
+    assert_eq!(queue.packets.len(), 2);
+    assert_eq!(queue.packets[0].packet.nonce, 1);
+    assert_eq!(queue.packets[1].packet.nonce, 2);
+
+    // A channel in its handshake is listed as opening.
+    assert_eq!(
+        query_connections(deps.as_ref(), false),
+        vec![ConnectionResponse {
+            channel: TEST_CHANNEL.to_string(),
+            state: ChannelState::Opening
+        }]
+    );
+
+    // Completing the handshake sends the queued packets in order.
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60);
+    let handshake_connect = mock_ibc_channel_connect_ack(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    let res = ibc_channel_connect(deps.as_mut(), env.clone(), handshake_connect).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    for (i, msg) in res.messages.iter().enumerate() {
+        let (channel, packet) = sent_packet(&msg.msg);
+        assert_eq!(channel, TEST_CHANNEL);
+        assert_eq!(packet.nonce, i as u64 + 1);
+        match &msg.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { timeout, .. }) => assert_eq!(
+                *timeout,
+                IbcTimeout::with_timestamp(env.block.time.plus_seconds(300))
+            ),
+            msg => panic!("not a packet: {:?}", msg),
+        }
+    }
+    assert!(query_queue(deps.as_ref(), TEST_CHANNEL).packets.is_empty());
+}
+
+#[test]
+fn drop_stale_queued_packets() {
+    let (mut deps, _res, info) = setup(None, None);
+
+    let handshake_open = mock_ibc_channel_open_init(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), handshake_open).unwrap();
+
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let mut env = mock_env();
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    env.block.time = env.block.time.plus_seconds(3600);
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let drop = ExecuteMsg::DropQueued {
+        channel: TEST_CHANNEL.to_string(),
+        queued_before: env.block.time,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        drop.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+
+    let res = execute(deps.as_mut(), mock_env(), info, drop).unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("dropped", "1".to_string())));
+
+    let queue = query_queue(deps.as_ref(), TEST_CHANNEL);
+    assert_eq!(queue.packets.len(), 1);
+    assert_eq!(queue.packets[0].queued_at, env.block.time);
+}
+
+#[test]
+fn typed_ack() {
+    let pong = IbcPingResponse {
+        result: "pong".to_string(),
+        payload: None,
+        sha256: None,
+    };
+
+    let ack = Ack::success_data(&pong).unwrap();
+    assert_eq!(
+        Ack::<IbcPingResponse>::decode(&ack).unwrap(),
+        Ack::Result(pong.clone())
+    );
+
+    // Conversions keep the serialized form.
+    let raw = Ack::try_from(&IbcAcknowledgement::new(ack.clone())).unwrap();
+    assert_eq!(raw.clone().into_typed().unwrap(), Ack::Result(pong));
+    assert_eq!(IbcAcknowledgement::try_from(raw).unwrap().data, ack);
+
+    let ack = Ack::fail("boom").unwrap();
+    assert_eq!(
+        Ack::<IbcPingResponse>::decode(&ack).unwrap(),
+        Ack::Error("boom".to_string())
+    );
+
+    // Result data that is no `IbcPingResponse` is an error, not a panic.
+    Ack::<IbcPingResponse>::decode(&Ack::success().unwrap()).unwrap_err();
+    Ack::<IbcPingResponse>::decode(b"not an ack").unwrap_err();
+}
+
+//Sends a batch of a ping and an execute from `chain_a` and receives it on `chain_b`.
+fn receive_batch(
+    chain_a: DepsMut,
+    mut chain_b: DepsMut,
+    atomic: bool,
+) -> (DispatcherPacket<IbcExecuteMsg>, IbcReceiveResponse) {
+    let msg = ExecuteMsg::Admin(AdminMsg::SetAllowedContracts {
+        contracts: Some(vec!["target".to_string()]),
+    });
+    let info = mock_info(CREATER_ADDR, &[]);
+    execute(chain_b.branch(), mock_env(), info, msg).unwrap();
+
+    let msgs = vec![
+        ping(),
+        IbcExecuteMsg::Execute {
+            contract: "target".to_string(),
+            msg: Binary::from(br#"{"mint":{}}"#),
+        },
+    ];
+    let msg = ExecuteMsg::Batch {
+        channel: TEST_CHANNEL.to_string(),
+        msgs,
+        atomic,
+        force: None,
+    };
+    let res = execute(chain_a, mock_env(), mock_info(CREATER_ADDR, &[]), msg).unwrap();
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+    let res = receive_packet(&Dispatcher, chain_b, mock_env(), msg);
+    (packet, res)
+}
+
+#[test]
+fn best_effort_batch() {
+    let (mut chain_a, _res, _info) = setup(None, None);
+    let (mut chain_b, _res, _info) = setup(None, None);
+    connect(chain_a.as_mut(), TEST_CHANNEL);
+    connect(chain_b.as_mut(), TEST_CHANNEL);
+
+    let (packet, res) = receive_batch(chain_a.as_mut(), chain_b.as_mut(), false);
+
+    // Every message runs in its own submessage, whose reply writes the ack.
+    assert_eq!(res.messages.len(), 2);
+    for (i, msg) in res.messages.iter().enumerate() {
+        assert_eq!(msg.id, BATCH_REPLY_ID + i as u64);
+        assert_eq!(msg.reply_on, ReplyOn::Always);
+    }
+    let handle = |i: usize| -> ExecuteMsg {
+        match &res.messages[i].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => from_binary(msg).unwrap(),
+            msg => panic!("not an execute: {:?}", msg),
+        }
+    };
+    let info = mock_info(mock_env().contract.address.as_str(), &[]);
+
+    // The ping reports its own pong.
+    execute(chain_b.as_mut(), mock_env(), info.clone(), handle(0)).unwrap();
+    let handled = Reply {
+        id: BATCH_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res_ping = reply(chain_b.as_mut(), mock_env(), handled).unwrap();
+    let results: BatchResponse = Ack::parse(res_ping.data.unwrap());
+    let pong: IbcPingResponse = from_binary(&results.results[0].clone().unwrap()).unwrap();
+    assert_eq!(pong.result, "pong");
+
+    // The submessage executes the message on the target contract.
+    let res = execute(chain_b.as_mut(), mock_env(), info, handle(1)).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "target".to_string(),
+            msg: Binary::from(br#"{"mint":{}}"#),
+            funds: vec![],
+        })
+    );
+
+    // The target fails, only its result turns into an error.
+    let failed = Reply {
+        id: BATCH_REPLY_ID + 1,
+        result: SubMsgResult::Err("mint failed".to_string()),
+    };
+    let res = reply(chain_b.as_mut(), mock_env(), failed).unwrap();
+    let ack = res.data.unwrap();
+    let results: BatchResponse = Ack::parse(ack.clone());
+    assert_eq!(
+        results.results,
+        vec![
+            Ack::Result(to_binary(&pong).unwrap()),
+            Ack::Error("mint failed".to_string())
+        ]
+    );
+
+    // The sender learns which messages succeeded.
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, IbcAcknowledgement::new(ack)).unwrap();
+    let res = ibc_packet_ack(chain_a.as_mut(), mock_env(), msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("succeeded", "1".to_string())));
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("failed", "1".to_string())));
+}
+
+#[test]
+fn atomic_batch() {
+    let (mut chain_a, _res, _info) = setup(None, None);
+    let (mut chain_b, _res, _info) = setup(None, None);
+    connect(chain_a.as_mut(), TEST_CHANNEL);
+    connect(chain_b.as_mut(), TEST_CHANNEL);
+
+    let (_packet, res) = receive_batch(chain_a.as_mut(), chain_b.as_mut(), true);
+
+    // All messages run in one submessage and fail together.
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, BATCH_REPLY_ID);
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Always);
+
+    let failed = Reply {
+        id: BATCH_REPLY_ID,
+        result: SubMsgResult::Err("mint failed".to_string()),
+    };
+    let res = reply(chain_b.as_mut(), mock_env(), failed).unwrap();
+    let results: BatchResponse = Ack::parse(res.data.unwrap());
+    assert_eq!(
+        results.results,
+        vec![Ack::Error("mint failed".to_string()); 2]
+    );
+}
+
+#[test]
+fn invalid_batch() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let msg = ExecuteMsg::Batch {
+        channel: TEST_CHANNEL.to_string(),
+        msgs: vec![],
+        atomic: true,
+        force: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "batch must not be empty");
+
+    let msg = ExecuteMsg::Batch {
+        channel: TEST_CHANNEL.to_string(),
+        msgs: vec![IbcExecuteMsg::Forward {
+            path: vec![],
+            id: 1,
+            inner: Box::new(ping()),
+        }],
+        atomic: true,
+        force: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "batches can only contain ping and execute messages"
+    );
+
+    // Only the dispatcher runs the messages of a received batch.
+    let msg = ExecuteMsg::HandleBatch {
+        first: 0,
+        msgs: vec![ping()],
+    };
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+}
+
+#[test]
+fn receive_handles_packet_in_submessage() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let packet = envelope(1, ping());
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+    let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+
+    // The handler runs in a submessage calling the dispatcher itself.
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, RECEIVE_REPLY_ID);
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Always);
+    let handle: ExecuteMsg = match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => {
+            assert_eq!(contract_addr, mock_env().contract.address.as_str());
+            from_binary(msg).unwrap()
+        }
+        msg => panic!("not an execute: {:?}", msg),
+    };
+
+    // Nobody else can run the handler.
+    let err = execute(deps.as_mut(), mock_env(), info, handle.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+
+    let contract = mock_info(mock_env().contract.address.as_str(), &[]);
+    let res = execute(deps.as_mut(), mock_env(), contract.clone(), handle.clone()).unwrap();
+    let ack = res.data.unwrap();
+    let pong: IbcPingResponse = Ack::parse(ack.clone());
+    assert_eq!(pong.result, "pong");
+
+    // The packet is handled only once.
+    let err = execute(deps.as_mut(), mock_env(), contract, handle).unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+
+    // The reply writes the data of the handler as ack.
+    let handled = Reply {
+        id: RECEIVE_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(execute_response_data(ack.clone())),
+        }),
+    };
+    let res = reply(deps.as_mut(), mock_env(), handled).unwrap();
+    assert_eq!(res.data, Some(ack));
+
+    // A failed handler has been rolled back, the reply writes an error ack.
+    let failed = Reply {
+        id: RECEIVE_REPLY_ID,
+        result: SubMsgResult::Err("out of gas".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+    let ack: Ack = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(ack.unwrap_err(), "out of gas");
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("error", "out of gas".to_string())));
+}
+
+#[test]
+fn validation_errors_skip_handler() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+    let msg = ExecuteMsg::Admin(AdminMsg::Pause {});
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Errors the sender has to see are written by `ibc_packet_receive`
+    // itself, the chain would redact them in the reply of the handler.
+    let packets = [
+        (envelope(1, ping()), ContractError::Paused {}),
+        (
+            envelope(2, IbcExecuteMsg::Admin(AdminMsg::Unpause {})),
+            ContractError::NotController {
+                port: "their_port".to_string(),
+            },
+        ),
+    ];
+    for (packet, error) in packets {
+        let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.messages.is_empty());
+        let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+        assert_eq!(ack.unwrap_err(), error.to_string());
+    }
+}
+
+#[test]
+fn forged_handle_packet() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+    let contract = mock_env().contract.address.to_string();
+
+    // Even if the admin allows it, the dispatcher can not be executed.
+    let msg = ExecuteMsg::Admin(AdminMsg::SetAllowedContracts {
+        contracts: Some(vec![contract.clone()]),
+    });
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // A remote sender tries to run an admin command as if it came from
+    // another channel.
+    let forged = ExecuteMsg::HandlePacket(HandlePacketMsg {
+        msg: IbcExecuteMsg::Admin(AdminMsg::Pause {}),
+    });
+    let packet = envelope(
+        1,
+        IbcExecuteMsg::Execute {
+            contract: contract.clone(),
+            msg: to_binary(&forged).unwrap(),
+        },
+    );
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    assert!(res.messages.is_empty());
+    let ack: Ack = from_binary(&res.acknowledgement).unwrap();
+    assert_eq!(
+        ack.unwrap_err(),
+        format!(
+            "contract_not_allowed: contract ({}) is not allowed",
+            contract
+        )
+    );
+
+    // Outside of a receive there is no packet to handle, whatever the
+    // message claims.
+    let dispatcher = mock_info(&contract, &[]);
+    let err = execute(deps.as_mut(), mock_env(), dispatcher.clone(), forged).unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+    let batch = ExecuteMsg::HandleBatch {
+        first: 0,
+        msgs: vec![IbcExecuteMsg::Admin(AdminMsg::Pause {})],
+    };
+    let err = execute(deps.as_mut(), mock_env(), dispatcher, batch).unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+    assert!(!CONFIG.load(deps.as_ref().storage).unwrap().paused);
+}
+
+#[test]
+fn undecodable_ack() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    // Result data that does not decode is reported, the ack still counts.
+    let garbage = IbcAcknowledgement::new(Ack::success().unwrap());
+    let msgs = [
+        ExecuteMsg::Ping {
+            channel: TEST_CHANNEL.to_string(),
+            payload: None,
+            expect_echo: None,
+        },
+        ExecuteMsg::SyncCounter {
+            channel: TEST_CHANNEL.to_string(),
+            force: None,
+        },
+        ExecuteMsg::Batch {
+            channel: TEST_CHANNEL.to_string(),
+            msgs: vec![ping()],
+            atomic: false,
+            force: None,
+        },
+    ];
+    for msg in msgs {
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let (_, packet) = sent_packet(&res.messages[0].msg);
+        let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, garbage.clone()).unwrap();
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        let error = res.attributes.iter().find(|a| a.key == "error").unwrap();
+        assert!(error.value.starts_with("invalid ack"), "{}", error.value);
+    }
+
+    // The undecodable pong is recorded as corruption.
+    let health = query_health(deps.as_ref(), TEST_CHANNEL);
+    assert_eq!(health.corruptions, 1);
+    assert_eq!(query_count(deps.as_ref(), TEST_CHANNEL), 0);
+}
+
+fn query_health(deps: Deps, channel: &str) -> ChannelHealthResponse {
+    let msg = QueryMsg::GetChannelHealth {
+        channel: channel.to_string(),
+    };
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+//Returns the status of the `dispatcher_health` event of a response, if any.
+fn health_event(events: &[Event]) -> Option<String> {
+    events
+        .iter()
+        .find(|e| e.ty == "dispatcher_health")
+        .and_then(|e| e.attributes.iter().find(|a| a.key == "status"))
+        .map(|a| a.value.clone())
+}
+
+#[test]
+fn channel_health() {
+    let msg = InstantiateMsg {
+        admin: None,
+        degraded_after: Some(2),
+        unhealthy_after: Some(3),
+        controller_port: None,
+        max_packet_bytes: None,
+        max_execute_depth: None,
+    };
+    let (mut deps, _res, info) = setup(None, Some(msg));
+    connect(deps.as_mut(), TEST_CHANNEL);
+    assert_eq!(
+        query_health(deps.as_ref(), TEST_CHANNEL).status,
+        HealthStatus::Healthy
+    );
+
+    // A timeout and an error ack degrade the channel.
+    let packet = envelope(1, ping());
+    let msg = mock_ibc_packet_timeout(TEST_CHANNEL, &packet).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(health_event(&res.events), None);
+
+    let error = IbcAcknowledgement::new(Ack::fail("boom").unwrap());
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, error.clone()).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(health_event(&res.events).as_deref(), Some("degraded"));
+    assert!(res.events.contains(&Event::from(DispatcherEvent::Health {
+        channel: TEST_CHANNEL.to_string(),
+        previous: "healthy".to_string(),
+        status: "degraded".to_string(),
+    })));
+
+    let msg = mock_ibc_packet_timeout(TEST_CHANNEL, &packet).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(health_event(&res.events).as_deref(), Some("unhealthy"));
+    assert_eq!(
+        query_health(deps.as_ref(), TEST_CHANNEL),
+        ChannelHealthResponse {
+            channel: TEST_CHANNEL.to_string(),
+            status: HealthStatus::Unhealthy,
+            consecutive_timeouts: 2,
+            consecutive_errors: 1,
+            corruptions: 0,
+        }
+    );
+
+    // New dispatches are refused unless forced.
+    let batch = |force| ExecuteMsg::Batch {
+        channel: TEST_CHANNEL.to_string(),
+        msgs: vec![ping()],
+        atomic: true,
+        force,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), batch(None)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "channel (channel-1) is unhealthy, set force to send anyway"
+    );
+    execute(deps.as_mut(), mock_env(), info.clone(), batch(Some(true))).unwrap();
+    let sync = ExecuteMsg::SyncCounter {
+        channel: TEST_CHANNEL.to_string(),
+        force: None,
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), sync).unwrap_err();
+    let send_admin = ExecuteMsg::SendAdmin {
+        channel: TEST_CHANNEL.to_string(),
+        msg: AdminMsg::Pause {},
+        force: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), send_admin).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "channel (channel-1) is unhealthy, set force to send anyway"
+    );
+
+    // Pings still go out, a successful one restores the channel.
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let pong = IbcAcknowledgement::new(
+        Ack::success_data(&IbcPingResponse {
+            result: "pong".to_string(),
+            payload: None,
+            sha256: None,
+        })
+        .unwrap(),
+    );
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, pong).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(health_event(&res.events).as_deref(), Some("healthy"));
+    assert_eq!(
+        query_health(deps.as_ref(), TEST_CHANNEL),
+        ChannelHealthResponse {
+            channel: TEST_CHANNEL.to_string(),
+            status: HealthStatus::Healthy,
+            consecutive_timeouts: 0,
+            consecutive_errors: 0,
+            corruptions: 0,
+        }
+    );
+    execute(deps.as_mut(), mock_env(), info, batch(None)).unwrap();
+}
+
+#[test]
+fn invalid_health_thresholds() {
+    let mut deps = mock_dependencies();
+    for (degraded_after, unhealthy_after) in [(Some(0), None), (Some(5), Some(4))] {
+        let msg = InstantiateMsg {
+            admin: None,
+            degraded_after,
+            unhealthy_after,
+            controller_port: None,
+            max_packet_bytes: None,
+            max_execute_depth: None,
+        };
+        let err =
+            instantiate(deps.as_mut(), mock_env(), mock_info(CREATER_ADDR, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHealthThresholds {}));
+    }
+}
+
+//Receives `msg` from the remote sender and returns the decoded ack.
+fn receive_msg(deps: DepsMut, nonce: u64, msg: IbcExecuteMsg) -> (IbcReceiveResponse, Ack) {
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &envelope(nonce, msg)).unwrap();
+    let res = receive_packet(&Dispatcher, deps, mock_env(), msg);
+    let ack = from_binary(&res.acknowledgement).unwrap();
+    (res, ack)
+}
+
+#[test]
+fn admin_packets_from_controller() {
+    let msg = InstantiateMsg {
+        admin: None,
+        degraded_after: None,
+        unhealthy_after: None,
+        // The counterparty port of mocked channels.
+        controller_port: Some("their_port".to_string()),
+        max_packet_bytes: None,
+        max_execute_depth: None,
+    };
+    let (mut deps, _res, info) = setup(None, Some(msg));
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    // Pausing stops sending and receiving.
+    let (res, ack) = receive_msg(deps.as_mut(), 1, IbcExecuteMsg::Admin(AdminMsg::Pause {}));
+    assert!(ack.is_ok());
+    assert!(res.attributes.contains(&Attribute::new("command", "pause")));
+
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "dispatcher is paused");
+    let (_, ack) = receive_msg(deps.as_mut(), 2, ping());
+    assert_eq!(ack.unwrap_err(), "dispatcher is paused");
+
+    let (_, ack) = receive_msg(deps.as_mut(), 3, IbcExecuteMsg::Admin(AdminMsg::Unpause {}));
+    assert!(ack.is_ok());
+    let (_, ack) = receive_msg(deps.as_mut(), 4, ping());
+    assert!(ack.is_ok());
+
+    // Execute messages may only call allowed contracts.
+    let msg = AdminMsg::SetAllowedContracts {
+        contracts: Some(vec!["target".to_string()]),
+    };
+    let (_, ack) = receive_msg(deps.as_mut(), 5, IbcExecuteMsg::Admin(msg));
+    assert!(ack.is_ok());
+    let execute_on = |contract: &str| IbcExecuteMsg::Execute {
+        contract: contract.to_string(),
+        msg: Binary::from(br#"{"mint":{}}"#),
+    };
+    let (_, ack) = receive_msg(deps.as_mut(), 6, execute_on("other"));
+    assert_eq!(
+        ack.unwrap_err(),
+        "contract_not_allowed: contract (other) is not allowed"
+    );
+    let (res, ack) = receive_msg(deps.as_mut(), 7, execute_on("target"));
+    assert!(ack.is_ok());
+    assert_eq!(res.messages.len(), 1);
+
+    // Config updates are validated like on instantiation.
+    let update = |degraded_after| AdminMsg::UpdateConfig {
+        admin: Some("new-admin".to_string()),
+        controller_port: None,
+        degraded_after: Some(degraded_after),
+        unhealthy_after: None,
+    };
+    let (_, ack) = receive_msg(deps.as_mut(), 8, IbcExecuteMsg::Admin(update(11)));
+    assert_eq!(
+        ack.unwrap_err(),
+        "health thresholds must be above zero, the degraded one at most the unhealthy one"
+    );
+    let (_, ack) = receive_msg(deps.as_mut(), 9, IbcExecuteMsg::Admin(update(5)));
+    assert!(ack.is_ok());
+    let msg = ExecuteMsg::Admin(AdminMsg::Pause {});
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+    execute(deps.as_mut(), mock_env(), mock_info("new-admin", &[]), msg).unwrap();
+}
+
+#[test]
+fn admin_packets_need_controller() {
+    let (mut deps, _res, _info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let (_, ack) = receive_msg(deps.as_mut(), 1, IbcExecuteMsg::Admin(AdminMsg::Pause {}));
+    assert_eq!(
+        ack.unwrap_err(),
+        "admin packets are only accepted from the controller, got port (their_port)"
+    );
+    let (_, ack) = receive_msg(deps.as_mut(), 2, ping());
+    assert!(ack.is_ok());
+}
+
+#[test]
+fn send_admin() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let msg = ExecuteMsg::SendAdmin {
+        channel: TEST_CHANNEL.to_string(),
+        msg: AdminMsg::Pause {},
+        force: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("someone", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "unauthorized");
+
+    // Sent on behalf of the contract, its port identifies the controller.
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let (channel, packet) = sent_packet(&res.messages[0].msg);
+    assert_eq!(channel, TEST_CHANNEL);
+    assert_eq!(packet.sender.address, mock_env().contract.address.as_str());
+    assert_eq!(packet.msg, IbcExecuteMsg::Admin(AdminMsg::Pause {}));
+
+    let ack = IbcAcknowledgement::new(Ack::fail("unauthorized").unwrap());
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("error", "unauthorized")));
+}
+
+#[test]
+fn echo_ping() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    let payload = Binary::from(b"hello".as_slice());
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: Some(payload.clone()),
+        expect_echo: Some(true),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+
+    // The receiver echoes the payload together with its hash.
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &packet).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    let pong: IbcPingResponse = Ack::parse(res.acknowledgement.clone());
+    // sha256("hello")
+    let hash = Binary::from_base64("LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=").unwrap();
+    assert_eq!(
+        pong,
+        IbcPingResponse {
+            result: "pong".to_string(),
+            payload: Some(payload.clone()),
+            sha256: Some(hash.clone()),
+        }
+    );
+
+    let ack = mock_ibc_packet_ack(
+        TEST_CHANNEL,
+        &packet,
+        IbcAcknowledgement::new(res.acknowledgement),
+    )
+    .unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+    assert!(!res.attributes.iter().any(|a| a.key == "error"));
+    assert_eq!(query_count(deps.as_ref(), TEST_CHANNEL), 1);
+
+    // A tampered echo is recorded as corruption and not counted.
+    let tampered = IbcPingResponse {
+        payload: Some(Binary::from(b"hellO".as_slice())),
+        ..pong
+    };
+    let ack = IbcAcknowledgement::new(Ack::success_data(&tampered).unwrap());
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("error", "corrupted payload")));
+    assert_eq!(query_count(deps.as_ref(), TEST_CHANNEL), 1);
+    let health = query_health(deps.as_ref(), TEST_CHANNEL);
+    assert_eq!(health.corruptions, 1);
+    assert_eq!(health.consecutive_errors, 1);
+
+    // Without an echo only the hash is returned.
+    let msg = IbcExecuteMsg::Ping {
+        payload: Some(payload),
+        expect_echo: false,
+    };
+    let (_, ack) = receive_msg(deps.as_mut(), 1, msg);
+    let pong: IbcPingResponse = from_binary(&ack.unwrap()).unwrap();
+    assert_eq!(pong.payload, None);
+    assert_eq!(pong.sha256, Some(hash));
+
+    // Pings of dispatchers without payloads still decode.
+    let packet: IbcExecuteMsg = from_binary(&Binary::from(br#"{"ping":{}}"#)).unwrap();
+    assert_eq!(packet, ping());
+}
+
+#[test]
+fn inbound_limits() {
+    let msg = InstantiateMsg {
+        admin: None,
+        degraded_after: None,
+        unhealthy_after: None,
+        controller_port: None,
+        max_packet_bytes: Some(300),
+        max_execute_depth: Some(2),
+    };
+    let (mut deps, _res, info) = setup(None, Some(msg));
+    connect(deps.as_mut(), TEST_CHANNEL);
+    let msg = ExecuteMsg::Admin(AdminMsg::SetAllowedContracts {
+        contracts: Some(vec!["target".to_string()]),
+    });
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let execute_on = |contract: &str, msg: &[u8]| IbcExecuteMsg::Execute {
+        contract: contract.to_string(),
+        msg: Binary::from(msg),
+    };
+    let large = IbcExecuteMsg::Ping {
+        payload: Some(Binary::from(vec![0; 300])),
+        expect_echo: false,
+    };
+    let (res, ack) = receive_msg(deps.as_mut(), 1, large);
+    assert!(ack
+        .unwrap_err()
+        .starts_with("packet_too_large: packet has ("));
+    // Rejected before the packet is handled.
+    assert!(res.messages.is_empty());
+
+    let deep = execute_on("target", br#"{"mint":{"extension":{"name":"{{{"}}}"#);
+    let (_, ack) = receive_msg(deps.as_mut(), 1, deep);
+    assert_eq!(
+        ack.unwrap_err(),
+        "execute_too_deep: execute message is nested (3) levels deep, at most (2) are allowed"
+    );
+
+    let batch = IbcExecuteMsg::Batch {
+        msgs: vec![ping(), execute_on("other", br#"{"mint":{}}"#)],
+        atomic: false,
+    };
+    let (_, ack) = receive_msg(deps.as_mut(), 1, batch);
+    assert_eq!(
+        ack.unwrap_err(),
+        "contract_not_allowed: contract (other) is not allowed"
+    );
+
+    let (res, ack) = receive_msg(deps.as_mut(), 1, execute_on("target", br#"{"mint":{}}"#));
+    assert!(ack.is_ok());
+    assert_eq!(res.messages.len(), 1);
+
+    let msg = QueryMsg::GetRejectedPackets {
+        channel: TEST_CHANNEL.to_string(),
+    };
+    let rejected: RejectedPacketsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        rejected,
+        RejectedPacketsResponse {
+            channel: TEST_CHANNEL.to_string(),
+            packet_too_large: 1,
+            execute_too_deep: 1,
+            contract_not_allowed: 1,
+        }
+    );
+}
+
+#[test]
+fn dispatcher_events() {
+    let (mut deps, _res, info) = setup(None, None);
+
+    let handshake = mock_ibc_channel_open_init(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), handshake).unwrap();
+    let handshake = mock_ibc_channel_connect_ack(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    let res = ibc_channel_connect(deps.as_mut(), mock_env(), handshake).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::from(DispatcherEvent::Open {
+            channel: TEST_CHANNEL.to_string(),
+            counterparty_port: "their_port".to_string(),
+            counterparty_channel: "channel-7".to_string(),
+            version: IBC_VERSION.to_string(),
+        })]
+    );
+
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let send = &res.events[0];
+    assert_eq!(send.ty, "dispatcher_send");
+    assert_eq!(
+        send.attributes,
+        vec![
+            Attribute::new("channel", TEST_CHANNEL),
+            Attribute::new("sender", CREATER_ADDR),
+            Attribute::new("sender_chain_id", mock_env().block.chain_id),
+            Attribute::new("nonce", "1"),
+            Attribute::new("queued", "false"),
+        ]
+    );
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+
+    let msg = mock_ibc_packet_recv(TEST_CHANNEL, &envelope(1, ping())).unwrap();
+    let res = receive_packet(&Dispatcher, deps.as_mut(), mock_env(), msg);
+    assert!(res.events.contains(&Event::from(DispatcherEvent::Receive {
+        channel: TEST_CHANNEL.to_string(),
+        sender: REMOTE_SENDER.to_string(),
+        sender_chain_id: "remote-chain".to_string(),
+        nonce: 1,
+        sequence: 27,
+    })));
+
+    let ack = IbcAcknowledgement::new(Ack::fail("boom").unwrap());
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.events.contains(&Event::from(DispatcherEvent::Ack {
+        channel: TEST_CHANNEL.to_string(),
+        nonce: 1,
+        sequence: 29,
+        success: false,
+    })));
+
+    let msg = mock_ibc_packet_timeout(TEST_CHANNEL, &packet).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.events.contains(&Event::from(DispatcherEvent::Timeout {
+        channel: TEST_CHANNEL.to_string(),
+        nonce: 1,
+        sequence: 29,
+    })));
+
+    let channel = mock_ibc_channel_close_init(TEST_CHANNEL, IBC_ORDER, IBC_VERSION);
+    let res = ibc_channel_close(deps.as_mut(), mock_env(), channel).unwrap();
+    assert_eq!(
+        res.events,
+        vec![Event::from(DispatcherEvent::Close {
+            channel: TEST_CHANNEL.to_string(),
+        })]
+    );
+}
+
+fn query_counter_sync(deps: Deps, channel: &str) -> CounterSyncResponse {
+    let msg = QueryMsg::GetCounterSync {
+        channel: channel.to_string(),
+    };
+    from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+}
+
+#[test]
+fn sync_counter() {
+    let (mut deps, _res, info) = setup(None, None);
+    connect(deps.as_mut(), TEST_CHANNEL);
+
+    // Not synced yet.
+    let sync = query_counter_sync(deps.as_ref(), TEST_CHANNEL);
+    assert_eq!(sync.local_count, 0);
+    assert_eq!(sync.remote_count, None);
+    assert_eq!(sync.difference, None);
+
+    // The receiver answers with the pings it has received on the channel.
+    let (_, ack) = receive_msg(deps.as_mut(), 1, IbcExecuteMsg::SyncCounter {});
+    let res: IbcSyncCounterResponse = from_binary(&ack.unwrap()).unwrap();
+    assert_eq!(res.count, 0);
+    receive_msg(deps.as_mut(), 2, ping());
+    receive_msg(deps.as_mut(), 3, ping());
+    let (_, ack) = receive_msg(deps.as_mut(), 4, IbcExecuteMsg::SyncCounter {});
+    let res: IbcSyncCounterResponse = from_binary(&ack.unwrap()).unwrap();
+    assert_eq!(res.count, 2);
+
+    // One pong arrived, the counterparty has received three pings.
+    let msg = ExecuteMsg::Ping {
+        channel: TEST_CHANNEL.to_string(),
+        payload: None,
+        expect_echo: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+    let pong = IbcPingResponse {
+        result: "pong".to_string(),
+        payload: None,
+        sha256: None,
+    };
+    let ack = IbcAcknowledgement::new(Ack::success_data(&pong).unwrap());
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, ack).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let msg = ExecuteMsg::SyncCounter {
+        channel: TEST_CHANNEL.to_string(),
+        force: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let (_, packet) = sent_packet(&res.messages[0].msg);
+    assert_eq!(packet.msg, IbcExecuteMsg::SyncCounter {});
+    let ack =
+        IbcAcknowledgement::new(Ack::success_data(&IbcSyncCounterResponse { count: 3 }).unwrap());
+    let msg = mock_ibc_packet_ack(TEST_CHANNEL, &packet, ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("remote_count", "3")));
+
+    assert_eq!(
+        query_counter_sync(deps.as_ref(), TEST_CHANNEL),
+        CounterSyncResponse {
+            channel: TEST_CHANNEL.to_string(),
+            local_count: 1,
+            remote_count: Some(3),
+            difference: Some(2),
+            synced_at: Some(mock_env().block.time),
+        }
+    );
+}
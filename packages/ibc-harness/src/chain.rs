@@ -0,0 +1,659 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    from_slice, to_vec, Addr, Attribute, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env,
+    Event, IbcAcknowledgement, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg,
+    IbcChannelOpenMsg, IbcEndpoint, IbcMsg, IbcOrder, IbcPacket, IbcPacketAckMsg,
+    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, MessageInfo, Order, QuerierWrapper, Record, Reply,
+    ReplyOn, Storage, SubMsg, SubMsgResponse, SubMsgResult, WasmMsg,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{contract::ContractWrapper, error::HarnessError};
+
+/// Seconds between two blocks.
+pub const BLOCK_TIME: u64 = 5;
+
+/// Events and data of a transaction, as the chain reports them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppResponse {
+    pub events: Vec<Event>,
+    pub data: Option<Binary>,
+}
+
+impl AppResponse {
+    /// Events of type `ty`, custom events are prefixed with `wasm-`.
+    pub fn events_of(&self, ty: &str) -> Vec<&Event> {
+        self.events.iter().filter(|event| event.ty == ty).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelState {
+    Init,
+    TryOpen,
+    Open,
+    Closed,
+}
+
+/// One end of a channel, bound to the contract owning its port.
+#[derive(Clone, Debug)]
+pub struct ChannelEnd {
+    pub contract: Addr,
+    pub channel: IbcChannel,
+    pub state: ChannelState,
+    next_sequence: u64,
+}
+
+struct Instance {
+    code: Rc<ContractWrapper>,
+    storage: MockStorage,
+}
+
+/// State restored when a transaction or a submessage fails.
+struct Snapshot {
+    storages: BTreeMap<Addr, Vec<Record>>,
+    sequences: BTreeMap<String, u64>,
+    outbox: usize,
+}
+
+/// A chain in memory. Contracts get the port `wasm.<address>`, packets they
+/// send are kept in an outbox until the relayer picks them up.
+pub struct Chain {
+    block: BlockInfo,
+    api: MockApi,
+    querier: MockQuerier,
+    codes: Vec<Rc<ContractWrapper>>,
+    contracts: BTreeMap<Addr, Instance>,
+    channels: BTreeMap<String, ChannelEnd>,
+    outbox: Vec<IbcPacket>,
+}
+
+impl Chain {
+    pub fn new(chain_id: &str) -> Self {
+        let mut block = mock_env().block;
+        block.chain_id = chain_id.to_string();
+        Chain {
+            block,
+            api: MockApi::default(),
+            querier: MockQuerier::default(),
+            codes: vec![],
+            contracts: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            outbox: vec![],
+        }
+    }
+
+    pub fn block(&self) -> &BlockInfo {
+        &self.block
+    }
+
+    /// Moves `blocks` blocks ahead, `BLOCK_TIME` seconds each.
+    pub fn advance_blocks(&mut self, blocks: u64) {
+        self.block.height += blocks;
+        self.block.time = self.block.time.plus_seconds(blocks * BLOCK_TIME);
+    }
+
+    pub fn store_code(&mut self, code: ContractWrapper) -> u64 {
+        self.codes.push(Rc::new(code));
+        self.codes.len() as u64
+    }
+
+    pub fn instantiate<T: Serialize>(
+        &mut self,
+        code_id: u64,
+        sender: &str,
+        msg: &T,
+    ) -> Result<Addr, HarnessError> {
+        let code = self
+            .codes
+            .get(code_id as usize - 1)
+            .cloned()
+            .ok_or_else(|| HarnessError::UnsupportedMessage {
+                msg: format!("instantiate unknown code ({})", code_id),
+            })?;
+        let address = Addr::unchecked(format!("contract{}", self.contracts.len()));
+        self.contracts.insert(
+            address.clone(),
+            Instance {
+                code,
+                storage: MockStorage::new(),
+            },
+        );
+
+        let msg = to_vec(msg)?;
+        let info = message_info(sender);
+        let res = self.transaction(|chain| {
+            let res = chain.call(&address, |code, deps, env| {
+                (code.instantiate)(deps, env, info, &msg)
+            })?;
+            chain.finish(&address, res.messages, res.attributes, res.events, res.data)
+        });
+        match res {
+            Ok(_) => Ok(address),
+            Err(err) => {
+                self.contracts.remove(&address);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn execute<T: Serialize>(
+        &mut self,
+        contract: &Addr,
+        sender: &str,
+        msg: &T,
+    ) -> Result<AppResponse, HarnessError> {
+        let msg = to_vec(msg)?;
+        self.transaction(|chain| chain.execute_wasm(contract, &Addr::unchecked(sender), &msg))
+    }
+
+    pub fn query<T: DeserializeOwned, Q: Serialize>(
+        &self,
+        contract: &Addr,
+        msg: &Q,
+    ) -> Result<T, HarnessError> {
+        let instance = self.instance(contract)?;
+        let deps = Deps {
+            storage: &instance.storage,
+            api: &self.api,
+            querier: QuerierWrapper::new(&self.querier),
+        };
+        let res =
+            (instance.code.query)(deps, self.env(contract), &to_vec(msg)?).map_err(|error| {
+                HarnessError::Contract {
+                    contract: contract.to_string(),
+                    error,
+                }
+            })?;
+        Ok(from_slice(&res)?)
+    }
+
+    pub fn port_of(contract: &Addr) -> String {
+        format!("wasm.{}", contract)
+    }
+
+    pub fn channel(&self, channel: &str) -> Option<&ChannelEnd> {
+        self.channels.get(channel)
+    }
+
+    /// `OpenInit`, returns the id of the new channel.
+    pub(crate) fn open_init(
+        &mut self,
+        contract: &Addr,
+        counterparty_port: &str,
+        order: IbcOrder,
+        version: &str,
+    ) -> Result<String, HarnessError> {
+        let id = format!("channel-{}", self.channels.len());
+        let channel = IbcChannel::new(
+            IbcEndpoint {
+                port_id: Chain::port_of(contract),
+                channel_id: id.clone(),
+            },
+            IbcEndpoint {
+                port_id: counterparty_port.to_string(),
+                channel_id: String::new(),
+            },
+            order,
+            version,
+            "connection-0",
+        );
+        let msg = IbcChannelOpenMsg::new_init(channel.clone());
+        self.transaction(|chain| {
+            chain.call(contract, |code, deps, env| {
+                (ibc(code)?.open)(deps, env, msg)
+            })
+        })?;
+        self.add_channel(contract, channel, ChannelState::Init);
+        Ok(id)
+    }
+
+    /// `OpenTry`, returns the id of the new channel.
+    pub(crate) fn open_try(
+        &mut self,
+        contract: &Addr,
+        counterparty: IbcEndpoint,
+        order: IbcOrder,
+        version: &str,
+    ) -> Result<String, HarnessError> {
+        let id = format!("channel-{}", self.channels.len());
+        let channel = IbcChannel::new(
+            IbcEndpoint {
+                port_id: Chain::port_of(contract),
+                channel_id: id.clone(),
+            },
+            counterparty,
+            order,
+            version,
+            "connection-0",
+        );
+        let msg = IbcChannelOpenMsg::new_try(channel.clone(), version);
+        self.transaction(|chain| {
+            chain.call(contract, |code, deps, env| {
+                (ibc(code)?.open)(deps, env, msg)
+            })
+        })?;
+        self.add_channel(contract, channel, ChannelState::TryOpen);
+        Ok(id)
+    }
+
+    /// `OpenAck`, learns the channel id of the counterparty.
+    pub(crate) fn open_ack(
+        &mut self,
+        channel: &str,
+        counterparty_channel: &str,
+    ) -> Result<AppResponse, HarnessError> {
+        let end = self.channel_end(channel)?;
+        end.channel.counterparty_endpoint.channel_id = counterparty_channel.to_string();
+        let (contract, channel) = (end.contract.clone(), end.channel.clone());
+        let (id, version) = (channel.endpoint.channel_id.clone(), channel.version.clone());
+        let res = self.connect(&contract, IbcChannelConnectMsg::new_ack(channel, version))?;
+        self.channel_end(&id)?.state = ChannelState::Open;
+        Ok(res)
+    }
+
+    /// `OpenConfirm`.
+    pub(crate) fn open_confirm(&mut self, channel: &str) -> Result<AppResponse, HarnessError> {
+        let end = self.channel_end(channel)?;
+        let (contract, channel) = (end.contract.clone(), end.channel.clone());
+        let id = channel.endpoint.channel_id.clone();
+        let res = self.connect(&contract, IbcChannelConnectMsg::new_confirm(channel))?;
+        self.channel_end(&id)?.state = ChannelState::Open;
+        Ok(res)
+    }
+
+    /// `CloseInit` if `init`, `CloseConfirm` otherwise.
+    pub(crate) fn close(&mut self, channel: &str, init: bool) -> Result<AppResponse, HarnessError> {
+        let end = self.channel_end(channel)?;
+        end.state = ChannelState::Closed;
+        let (contract, channel) = (end.contract.clone(), end.channel.clone());
+        let msg = if init {
+            IbcChannelCloseMsg::new_init(channel)
+        } else {
+            IbcChannelCloseMsg::new_confirm(channel)
+        };
+        self.transaction(|chain| {
+            let res = chain.call(&contract, |code, deps, env| {
+                (ibc(code)?.close)(deps, env, msg)
+            })?;
+            chain.finish(&contract, res.messages, res.attributes, res.events, None)
+        })
+    }
+
+    /// Takes the packets sent since the last call.
+    pub(crate) fn take_outbox(&mut self) -> Vec<IbcPacket> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Runs `ibc_packet_receive` and returns the ack written for the packet.
+    /// The data of a reply replaces the ack of the contract, as on wasmd.
+    pub(crate) fn receive(
+        &mut self,
+        packet: &IbcPacket,
+        relayer: &Addr,
+    ) -> Result<(AppResponse, Binary), HarnessError> {
+        let contract = self.open_end(&packet.dest.channel_id)?.contract.clone();
+        let msg: IbcPacketReceiveMsg = from_slice(&to_vec(&PacketMsg { packet, relayer })?)?;
+        let res = self.transaction(|chain| {
+            let res = chain.call(&contract, |code, deps, env| {
+                (ibc(code)?.receive)(deps, env, msg)
+            })?;
+            chain.finish(
+                &contract,
+                res.messages,
+                res.attributes,
+                res.events,
+                Some(res.acknowledgement),
+            )
+        })?;
+        let ack = res.data.clone().unwrap_or_default();
+        Ok((res, ack))
+    }
+
+    pub(crate) fn ack(
+        &mut self,
+        packet: &IbcPacket,
+        ack: Binary,
+        relayer: &Addr,
+    ) -> Result<AppResponse, HarnessError> {
+        let contract = self.channel_end(&packet.src.channel_id)?.contract.clone();
+        let msg: IbcPacketAckMsg = from_slice(&to_vec(&AckMsg {
+            acknowledgement: IbcAcknowledgement::new(ack),
+            original_packet: packet,
+            relayer,
+        })?)?;
+        self.transaction(|chain| {
+            let res = chain.call(&contract, |code, deps, env| {
+                (ibc(code)?.ack)(deps, env, msg)
+            })?;
+            chain.finish(&contract, res.messages, res.attributes, res.events, None)
+        })
+    }
+
+    pub(crate) fn timeout(
+        &mut self,
+        packet: &IbcPacket,
+        relayer: &Addr,
+    ) -> Result<AppResponse, HarnessError> {
+        let contract = self.channel_end(&packet.src.channel_id)?.contract.clone();
+        let msg: IbcPacketTimeoutMsg = from_slice(&to_vec(&PacketMsg { packet, relayer })?)?;
+        self.transaction(|chain| {
+            let res = chain.call(&contract, |code, deps, env| {
+                (ibc(code)?.timeout)(deps, env, msg)
+            })?;
+            chain.finish(&contract, res.messages, res.attributes, res.events, None)
+        })
+    }
+
+    /// Whether `packet` can no longer be received on this chain.
+    pub(crate) fn timed_out(&self, packet: &IbcPacket) -> bool {
+        let by_height = packet
+            .timeout
+            .block()
+            .map_or(false, |block| self.block.height >= block.height);
+        let by_time = packet
+            .timeout
+            .timestamp()
+            .map_or(false, |time| self.block.time >= time);
+        by_height || by_time
+    }
+
+    fn connect(
+        &mut self,
+        contract: &Addr,
+        msg: IbcChannelConnectMsg,
+    ) -> Result<AppResponse, HarnessError> {
+        self.transaction(|chain| {
+            let res = chain.call(contract, |code, deps, env| {
+                (ibc(code)?.connect)(deps, env, msg)
+            })?;
+            chain.finish(contract, res.messages, res.attributes, res.events, None)
+        })
+    }
+
+    fn add_channel(&mut self, contract: &Addr, channel: IbcChannel, state: ChannelState) {
+        self.channels.insert(
+            channel.endpoint.channel_id.clone(),
+            ChannelEnd {
+                contract: contract.clone(),
+                channel,
+                state,
+                next_sequence: 1,
+            },
+        );
+    }
+
+    fn channel_end(&mut self, channel: &str) -> Result<&mut ChannelEnd, HarnessError> {
+        self.channels
+            .get_mut(channel)
+            .ok_or_else(|| HarnessError::UnknownChannel {
+                channel: channel.to_string(),
+            })
+    }
+
+    fn open_end(&mut self, channel: &str) -> Result<&mut ChannelEnd, HarnessError> {
+        let end = self.channel_end(channel)?;
+        match end.state {
+            ChannelState::Open => Ok(end),
+            _ => Err(HarnessError::ClosedChannel {
+                channel: channel.to_string(),
+            }),
+        }
+    }
+
+    fn instance(&self, contract: &Addr) -> Result<&Instance, HarnessError> {
+        self.contracts
+            .get(contract)
+            .ok_or_else(|| HarnessError::UnknownContract {
+                address: contract.to_string(),
+            })
+    }
+
+    fn env(&self, contract: &Addr) -> Env {
+        let mut env = mock_env();
+        env.block = self.block.clone();
+        env.contract.address = contract.clone();
+        env
+    }
+
+    /// Calls an entry point of `contract` with its own storage.
+    fn call<R>(
+        &mut self,
+        contract: &Addr,
+        entry_point: impl FnOnce(&ContractWrapper, DepsMut, Env) -> Result<R, String>,
+    ) -> Result<R, HarnessError> {
+        let env = self.env(contract);
+        let instance =
+            self.contracts
+                .get_mut(contract)
+                .ok_or_else(|| HarnessError::UnknownContract {
+                    address: contract.to_string(),
+                })?;
+        let code = instance.code.clone();
+        let deps = DepsMut {
+            storage: &mut instance.storage,
+            api: &self.api,
+            querier: QuerierWrapper::new(&self.querier),
+        };
+        entry_point(&code, deps, env).map_err(|error| HarnessError::Contract {
+            contract: contract.to_string(),
+            error,
+        })
+    }
+
+    fn execute_wasm(
+        &mut self,
+        contract: &Addr,
+        sender: &Addr,
+        msg: &[u8],
+    ) -> Result<AppResponse, HarnessError> {
+        let info = message_info(sender.as_str());
+        let res = self.call(contract, |code, deps, env| {
+            (code.execute)(deps, env, info, msg)
+        })?;
+        self.finish(contract, res.messages, res.attributes, res.events, res.data)
+    }
+
+    /// Turns the response of `contract` into events and runs its
+    /// submessages. Returns the data of the response, or of the last reply
+    /// that set some.
+    fn finish(
+        &mut self,
+        contract: &Addr,
+        messages: Vec<SubMsg<Empty>>,
+        attributes: Vec<Attribute>,
+        events: Vec<Event>,
+        mut data: Option<Binary>,
+    ) -> Result<AppResponse, HarnessError> {
+        let mut all_events = vec![];
+        if !attributes.is_empty() {
+            all_events.push(
+                Event::new("wasm")
+                    .add_attribute("_contract_address", contract)
+                    .add_attributes(attributes),
+            );
+        }
+        all_events.extend(events.into_iter().map(|event| {
+            Event::new(format!("wasm-{}", event.ty))
+                .add_attribute("_contract_address", contract)
+                .add_attributes(event.attributes)
+        }));
+
+        for msg in messages {
+            let snapshot = self.snapshot();
+            let result = match self.dispatch(contract, msg.msg) {
+                Ok(res) => {
+                    all_events.extend(res.events.clone());
+                    if !matches!(msg.reply_on, ReplyOn::Always | ReplyOn::Success) {
+                        continue;
+                    }
+                    SubMsgResult::Ok(SubMsgResponse {
+                        events: res.events,
+                        data: res.data,
+                    })
+                }
+                Err(err) => {
+                    self.restore(snapshot);
+                    if !matches!(msg.reply_on, ReplyOn::Always | ReplyOn::Error) {
+                        return Err(err);
+                    }
+                    SubMsgResult::Err(err.redacted())
+                }
+            };
+
+            let reply = Reply { id: msg.id, result };
+            let res = self.call(contract, |code, deps, env| match &code.reply {
+                Some(reply_fn) => reply_fn(deps, env, reply),
+                None => Err("no reply entry point".to_string()),
+            })?;
+            let res = self.finish(contract, res.messages, res.attributes, res.events, res.data)?;
+            all_events.extend(res.events);
+            if res.data.is_some() {
+                data = res.data;
+            }
+        }
+
+        Ok(AppResponse {
+            events: all_events,
+            data,
+        })
+    }
+
+    fn dispatch(&mut self, sender: &Addr, msg: CosmosMsg) -> Result<AppResponse, HarnessError> {
+        match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) if funds.is_empty() => {
+                let res = self.execute_wasm(&Addr::unchecked(contract_addr), sender, &msg)?;
+                Ok(AppResponse {
+                    events: res.events,
+                    data: res.data.map(execute_response_data),
+                })
+            }
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id,
+                data,
+                timeout,
+            }) => {
+                let end = self.open_end(&channel_id)?;
+                if &end.contract != sender {
+                    return Err(HarnessError::UnknownChannel {
+                        channel: channel_id,
+                    });
+                }
+                let packet = IbcPacket::new(
+                    data,
+                    end.channel.endpoint.clone(),
+                    end.channel.counterparty_endpoint.clone(),
+                    end.next_sequence,
+                    timeout,
+                );
+                end.next_sequence += 1;
+                self.outbox.push(packet);
+                Ok(AppResponse::default())
+            }
+            msg => Err(HarnessError::UnsupportedMessage {
+                msg: format!("{:?}", msg),
+            }),
+        }
+    }
+
+    /// Runs `f`, restores all state if it fails.
+    fn transaction<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<R, HarnessError>,
+    ) -> Result<R, HarnessError> {
+        let snapshot = self.snapshot();
+        let res = f(self);
+        if res.is_err() {
+            self.restore(snapshot);
+        }
+        res
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            storages: self
+                .contracts
+                .iter()
+                .map(|(address, instance)| {
+                    let records = instance
+                        .storage
+                        .range(None, None, Order::Ascending)
+                        .collect();
+                    (address.clone(), records)
+                })
+                .collect(),
+            sequences: self
+                .channels
+                .iter()
+                .map(|(id, end)| (id.clone(), end.next_sequence))
+                .collect(),
+            outbox: self.outbox.len(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        for (address, records) in snapshot.storages {
+            if let Some(instance) = self.contracts.get_mut(&address) {
+                let mut storage = MockStorage::new();
+                for (key, value) in records {
+                    storage.set(&key, &value);
+                }
+                instance.storage = storage;
+            }
+        }
+        for (id, sequence) in snapshot.sequences {
+            if let Some(end) = self.channels.get_mut(&id) {
+                end.next_sequence = sequence;
+            }
+        }
+        self.outbox.truncate(snapshot.outbox);
+    }
+}
+
+#[derive(Serialize)]
+struct PacketMsg<'a> {
+    packet: &'a IbcPacket,
+    relayer: &'a Addr,
+}
+
+#[derive(Serialize)]
+struct AckMsg<'a> {
+    acknowledgement: IbcAcknowledgement,
+    original_packet: &'a IbcPacket,
+    relayer: &'a Addr,
+}
+
+fn ibc(code: &ContractWrapper) -> Result<&crate::contract::IbcEntryPoints, String> {
+    code.ibc
+        .as_ref()
+        .ok_or_else(|| "no IBC entry points".to_string())
+}
+
+fn message_info(sender: &str) -> MessageInfo {
+    MessageInfo {
+        sender: Addr::unchecked(sender),
+        funds: vec![],
+    }
+}
+
+/// Encodes `data` as `MsgExecuteContractResponse`, the way wasmd passes it
+/// to the reply of an execute submessage.
+fn execute_response_data(data: Binary) -> Binary {
+    // Field 1, length delimited.
+    let mut encoded = vec![0x0a];
+    let mut len = data.len();
+    while len >= 0x80 {
+        encoded.push((len as u8) | 0x80);
+        len >>= 7;
+    }
+    encoded.push(len as u8);
+    encoded.extend_from_slice(&data);
+    Binary::from(encoded)
+}
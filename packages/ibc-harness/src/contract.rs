@@ -0,0 +1,107 @@
+use cosmwasm_std::{
+    from_slice, Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo, Reply, Response,
+};
+use serde::de::DeserializeOwned;
+
+type EntryPoint<M, R> = Box<dyn Fn(DepsMut, Env, M) -> Result<R, String>>;
+type ExecuteEntryPoint = Box<dyn Fn(DepsMut, Env, MessageInfo, &[u8]) -> Result<Response, String>>;
+type QueryEntryPoint = Box<dyn Fn(Deps, Env, &[u8]) -> Result<Binary, String>>;
+
+/// The entry points of a contract, with its messages still encoded and its
+/// errors turned into strings. Contracts have to be built with their
+/// `library` feature to call the entry points as functions.
+pub struct ContractWrapper {
+    pub(crate) instantiate: ExecuteEntryPoint,
+    pub(crate) execute: ExecuteEntryPoint,
+    pub(crate) query: QueryEntryPoint,
+    pub(crate) reply: Option<EntryPoint<Reply, Response>>,
+    pub(crate) ibc: Option<IbcEntryPoints>,
+}
+
+pub(crate) struct IbcEntryPoints {
+    pub open: EntryPoint<IbcChannelOpenMsg, ()>,
+    pub connect: EntryPoint<IbcChannelConnectMsg, IbcBasicResponse>,
+    pub close: EntryPoint<IbcChannelCloseMsg, IbcBasicResponse>,
+    pub receive: EntryPoint<IbcPacketReceiveMsg, IbcReceiveResponse>,
+    pub ack: EntryPoint<IbcPacketAckMsg, IbcBasicResponse>,
+    pub timeout: EntryPoint<IbcPacketTimeoutMsg, IbcBasicResponse>,
+}
+
+impl ContractWrapper {
+    pub fn new<I, X, Q, E1, E2, E3>(
+        instantiate: fn(DepsMut, Env, MessageInfo, I) -> Result<Response, E1>,
+        execute: fn(DepsMut, Env, MessageInfo, X) -> Result<Response, E2>,
+        query: fn(Deps, Env, Q) -> Result<Binary, E3>,
+    ) -> Self
+    where
+        I: DeserializeOwned + 'static,
+        X: DeserializeOwned + 'static,
+        Q: DeserializeOwned + 'static,
+        E1: ToString + 'static,
+        E2: ToString + 'static,
+        E3: ToString + 'static,
+    {
+        ContractWrapper {
+            instantiate: Box::new(move |deps, env, info, msg| {
+                instantiate(deps, env, info, decode(msg)?).map_err(|e| e.to_string())
+            }),
+            execute: Box::new(move |deps, env, info, msg| {
+                execute(deps, env, info, decode(msg)?).map_err(|e| e.to_string())
+            }),
+            query: Box::new(move |deps, env, msg| {
+                query(deps, env, decode(msg)?).map_err(|e| e.to_string())
+            }),
+            reply: None,
+            ibc: None,
+        }
+    }
+
+    pub fn with_reply<E: ToString + 'static>(
+        mut self,
+        reply: fn(DepsMut, Env, Reply) -> Result<Response, E>,
+    ) -> Self {
+        self.reply = Some(wrap(reply));
+        self
+    }
+
+    /// Adds the IBC entry points, in the order the chain calls them.
+    pub fn with_ibc<E1, E2, E3, E4, E5, E6>(
+        mut self,
+        open: fn(DepsMut, Env, IbcChannelOpenMsg) -> Result<(), E1>,
+        connect: fn(DepsMut, Env, IbcChannelConnectMsg) -> Result<IbcBasicResponse, E2>,
+        close: fn(DepsMut, Env, IbcChannelCloseMsg) -> Result<IbcBasicResponse, E3>,
+        receive: fn(DepsMut, Env, IbcPacketReceiveMsg) -> Result<IbcReceiveResponse, E4>,
+        ack: fn(DepsMut, Env, IbcPacketAckMsg) -> Result<IbcBasicResponse, E5>,
+        timeout: fn(DepsMut, Env, IbcPacketTimeoutMsg) -> Result<IbcBasicResponse, E6>,
+    ) -> Self
+    where
+        E1: ToString + 'static,
+        E2: ToString + 'static,
+        E3: ToString + 'static,
+        E4: ToString + 'static,
+        E5: ToString + 'static,
+        E6: ToString + 'static,
+    {
+        self.ibc = Some(IbcEntryPoints {
+            open: wrap(open),
+            connect: wrap(connect),
+            close: wrap(close),
+            receive: wrap(receive),
+            ack: wrap(ack),
+            timeout: wrap(timeout),
+        });
+        self
+    }
+}
+
+fn decode<T: DeserializeOwned>(msg: &[u8]) -> Result<T, String> {
+    from_slice(msg).map_err(|e| e.to_string())
+}
+
+fn wrap<M: 'static, R: 'static, E: ToString + 'static>(
+    entry_point: fn(DepsMut, Env, M) -> Result<R, E>,
+) -> EntryPoint<M, R> {
+    Box::new(move |deps, env, msg| entry_point(deps, env, msg).map_err(|e| e.to_string()))
+}
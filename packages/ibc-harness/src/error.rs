@@ -0,0 +1,42 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum HarnessError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("contract ({contract}) failed: {error}")]
+    Contract { contract: String, error: String },
+
+    #[error("no contract at ({address})")]
+    UnknownContract { address: String },
+
+    #[error("no channel ({channel})")]
+    UnknownChannel { channel: String },
+
+    #[error("channel ({channel}) is closed")]
+    ClosedChannel { channel: String },
+
+    #[error("unsupported message: {msg}")]
+    UnsupportedMessage { msg: String },
+
+    #[error("relaying has not settled after ({steps}) steps")]
+    RelayLimit { steps: usize },
+}
+
+impl HarnessError {
+    /// The error as wasmd passes it to a reply, redacted to its codespace
+    /// and code.
+    pub fn redacted(&self) -> String {
+        let code = match self {
+            // `ErrNotFound`
+            HarnessError::UnknownContract { .. } => 8,
+            // `ErrUnknownMsg`
+            HarnessError::UnsupportedMessage { .. } => 20,
+            // `ErrExecuteFailed`
+            _ => 5,
+        };
+        format!("codespace: wasm, code: {}", code)
+    }
+}
@@ -0,0 +1,16 @@
+//! Two chains in memory and a relayer between them, to test IBC contracts
+//! end to end with `cargo test`. Contracts are called through their entry
+//! points, so they have to be built with their `library` feature.
+
+pub mod chain;
+pub mod contract;
+pub mod error;
+pub mod relayer;
+
+#[cfg(test)]
+mod tests;
+
+pub use crate::chain::{AppResponse, Chain, ChannelState, BLOCK_TIME};
+pub use crate::contract::ContractWrapper;
+pub use crate::error::HarnessError;
+pub use crate::relayer::{Delivery, Fault, Network, Side};
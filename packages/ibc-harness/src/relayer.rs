@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+
+use cosmwasm_std::{Addr, Binary, IbcEndpoint, IbcOrder, IbcPacket};
+
+use crate::{
+    chain::{AppResponse, Chain, ChannelState},
+    error::HarnessError,
+};
+
+/// Relaying rounds after which `relay` gives up, packets that keep causing
+/// new packets would relay forever otherwise.
+pub const MAX_RELAY_ROUNDS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        }
+    }
+}
+
+/// Misbehaviour of the relayer, applied to the next relayed packets in the
+/// order faults were injected. Acks are always relayed right away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The packet is never received, it times out once its timeout passes
+    /// on the receiving chain.
+    Drop,
+    /// The packet is held back until the receiving chain is `blocks`
+    /// blocks further.
+    Delay { blocks: u64 },
+    /// The packet swaps places with the packet relayed after it.
+    Reorder,
+}
+
+/// What happened to a relayed packet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Delivery {
+    /// The packet was received and its ack relayed back.
+    Acked {
+        from: Side,
+        packet: IbcPacket,
+        ack: Binary,
+        /// Events of receiving the packet.
+        receive: AppResponse,
+        /// Events of handling the ack.
+        response: AppResponse,
+    },
+    /// The packet timed out, the sender has been told.
+    TimedOut {
+        from: Side,
+        packet: IbcPacket,
+        response: AppResponse,
+    },
+}
+
+#[derive(Clone, Debug)]
+struct InFlight {
+    from: Side,
+    packet: IbcPacket,
+}
+
+/// Two chains and a relayer between them. Nothing is relayed until `relay`
+/// is called.
+pub struct Network {
+    pub a: Chain,
+    pub b: Chain,
+    relayer: Addr,
+    faults: VecDeque<Fault>,
+    /// Delayed packets with the height of the receiving chain to relay
+    /// them at.
+    held: Vec<(u64, InFlight)>,
+    /// Dropped packets, kept to time them out.
+    dropped: Vec<InFlight>,
+}
+
+impl Network {
+    pub fn new(chain_a: &str, chain_b: &str) -> Self {
+        Network {
+            a: Chain::new(chain_a),
+            b: Chain::new(chain_b),
+            relayer: Addr::unchecked("relayer"),
+            faults: VecDeque::new(),
+            held: vec![],
+            dropped: vec![],
+        }
+    }
+
+    pub fn chain(&mut self, side: Side) -> &mut Chain {
+        match side {
+            Side::A => &mut self.a,
+            Side::B => &mut self.b,
+        }
+    }
+
+    /// Runs the channel handshake between `contract_a` on chain A and
+    /// `contract_b` on chain B. Returns the channel ids on A and on B.
+    pub fn open_channel(
+        &mut self,
+        contract_a: &Addr,
+        contract_b: &Addr,
+        order: IbcOrder,
+        version: &str,
+    ) -> Result<(String, String), HarnessError> {
+        let port_a = Chain::port_of(contract_a);
+        let port_b = Chain::port_of(contract_b);
+        let channel_a = self
+            .a
+            .open_init(contract_a, &port_b, order.clone(), version)?;
+        let counterparty = IbcEndpoint {
+            port_id: port_a,
+            channel_id: channel_a.clone(),
+        };
+        let channel_b = self.b.open_try(contract_b, counterparty, order, version)?;
+        self.a.open_ack(&channel_a, &channel_b)?;
+        self.b.open_confirm(&channel_b)?;
+        Ok((channel_a, channel_b))
+    }
+
+    /// Closes `channel` of chain `side`, then its counterparty.
+    pub fn close_channel(&mut self, side: Side, channel: &str) -> Result<(), HarnessError> {
+        let counterparty = self
+            .chain(side)
+            .channel(channel)
+            .ok_or_else(|| HarnessError::UnknownChannel {
+                channel: channel.to_string(),
+            })?
+            .channel
+            .counterparty_endpoint
+            .channel_id
+            .clone();
+        self.chain(side).close(channel, true)?;
+        self.chain(side.other()).close(&counterparty, false)?;
+        Ok(())
+    }
+
+    pub fn inject(&mut self, fault: Fault) {
+        self.faults.push_back(fault);
+    }
+
+    /// Moves both chains `blocks` blocks ahead.
+    pub fn advance_blocks(&mut self, blocks: u64) {
+        self.a.advance_blocks(blocks);
+        self.b.advance_blocks(blocks);
+    }
+
+    /// Packets delayed or dropped and not timed out yet.
+    pub fn pending(&self) -> usize {
+        self.held.len() + self.dropped.len()
+    }
+
+    /// Relays packets and acks until both chains have nothing left to send.
+    /// Dropped and delayed packets whose timeout has passed are timed out.
+    pub fn relay(&mut self) -> Result<Vec<Delivery>, HarnessError> {
+        let mut deliveries = vec![];
+
+        for in_flight in std::mem::take(&mut self.dropped) {
+            if self
+                .chain(in_flight.from.other())
+                .timed_out(&in_flight.packet)
+            {
+                deliveries.push(self.time_out(in_flight)?);
+            } else {
+                self.dropped.push(in_flight);
+            }
+        }
+
+        let mut due = vec![];
+        for (height, in_flight) in std::mem::take(&mut self.held) {
+            let dest = self.chain(in_flight.from.other());
+            if dest.timed_out(&in_flight.packet) || dest.block().height >= height {
+                due.push(in_flight);
+            } else {
+                self.held.push((height, in_flight));
+            }
+        }
+        for in_flight in due {
+            deliveries.push(self.deliver(in_flight)?);
+        }
+
+        for _ in 0..MAX_RELAY_ROUNDS {
+            let mut queue: VecDeque<InFlight> = self
+                .a
+                .take_outbox()
+                .into_iter()
+                .map(|packet| InFlight {
+                    from: Side::A,
+                    packet,
+                })
+                .chain(self.b.take_outbox().into_iter().map(|packet| InFlight {
+                    from: Side::B,
+                    packet,
+                }))
+                .collect();
+            if queue.is_empty() {
+                return Ok(deliveries);
+            }
+
+            while let Some(in_flight) = queue.pop_front() {
+                match self.faults.pop_front() {
+                    None => deliveries.push(self.deliver(in_flight)?),
+                    Some(Fault::Drop) => self.dropped.push(in_flight),
+                    Some(Fault::Delay { blocks }) => {
+                        let height = self.chain(in_flight.from.other()).block().height + blocks;
+                        self.held.push((height, in_flight));
+                    }
+                    Some(Fault::Reorder) => match queue.pop_front() {
+                        Some(next) => {
+                            deliveries.push(self.deliver(next)?);
+                            deliveries.push(self.deliver(in_flight)?);
+                        }
+                        // Nothing to swap with, the packet waits for the
+                        // next call.
+                        None => {
+                            let height = self.chain(in_flight.from.other()).block().height;
+                            self.held.push((height, in_flight));
+                        }
+                    },
+                }
+            }
+        }
+
+        Err(HarnessError::RelayLimit {
+            steps: MAX_RELAY_ROUNDS,
+        })
+    }
+
+    /// Receives the packet and relays the ack back, or times it out if it
+    /// can no longer be received.
+    fn deliver(&mut self, in_flight: InFlight) -> Result<Delivery, HarnessError> {
+        let from = in_flight.from;
+        let dest = self.chain(from.other());
+        let open = dest
+            .channel(&in_flight.packet.dest.channel_id)
+            .map_or(false, |end| end.state == ChannelState::Open);
+        if !open || dest.timed_out(&in_flight.packet) {
+            return self.time_out(in_flight);
+        }
+
+        let relayer = self.relayer.clone();
+        let packet = in_flight.packet;
+        let (receive, ack) = self.chain(from.other()).receive(&packet, &relayer)?;
+        let response = self.chain(from).ack(&packet, ack.clone(), &relayer)?;
+        Ok(Delivery::Acked {
+            from,
+            packet,
+            ack,
+            receive,
+            response,
+        })
+    }
+
+    fn time_out(&mut self, in_flight: InFlight) -> Result<Delivery, HarnessError> {
+        let relayer = self.relayer.clone();
+        let response = self
+            .chain(in_flight.from)
+            .timeout(&in_flight.packet, &relayer)?;
+        Ok(Delivery::TimedOut {
+            from: in_flight.from,
+            packet: in_flight.packet,
+            response,
+        })
+    }
+}
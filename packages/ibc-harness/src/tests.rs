@@ -0,0 +1,370 @@
+use cosmwasm_std::{from_binary, to_binary, Addr};
+use ibc_dispatcher_base::ack::Ack;
+use ibc_dispatcher_base::ibc::{IBC_ORDER, IBC_VERSION};
+use ibc_dispatcher_base::ibc_msg::{AdminMsg, BatchResponse, IbcExecuteMsg, IbcPingResponse};
+use ibc_dispatcher_base::msg::{
+    ChannelHealthResponse, CounterSyncResponse, ExecuteMsg, GetCounterResponse, InstantiateMsg,
+    QueryMsg,
+};
+
+use crate::{ContractWrapper, Delivery, Fault, HarnessError, Network, Side};
+
+const ADMIN: &str = "admin";
+
+fn dispatcher() -> ContractWrapper {
+    use ibc_dispatcher_base::{contract, ibc, ibc_ack, ibc_receive};
+    ContractWrapper::new(contract::instantiate, contract::execute, contract::query)
+        .with_reply(contract::reply)
+        .with_ibc(
+            ibc::ibc_channel_open,
+            ibc::ibc_channel_connect,
+            ibc::ibc_channel_close,
+            ibc_receive::ibc_packet_receive,
+            ibc_ack::ibc_packet_ack,
+            ibc::ibc_packet_timeout,
+        )
+}
+
+fn example() -> ContractWrapper {
+    use ibc_example::{contract, ibc};
+    ContractWrapper::new(contract::instantiate, contract::execute, contract::query)
+        .with_reply(contract::reply)
+        .with_ibc(
+            ibc::ibc_channel_open,
+            ibc::ibc_channel_connect,
+            ibc::ibc_channel_close,
+            ibc::ibc_packet_receive,
+            ibc::ibc_packet_ack,
+            ibc::ibc_packet_timeout,
+        )
+}
+
+/// A dispatcher on each chain, connected by a channel.
+fn setup() -> (Network, Addr, Addr, String, String) {
+    let mut network = Network::new("chain-a", "chain-b");
+    let msg = InstantiateMsg {
+        admin: None,
+        degraded_after: None,
+        unhealthy_after: None,
+        controller_port: None,
+        max_packet_bytes: None,
+        max_execute_depth: None,
+    };
+    let code_a = network.a.store_code(dispatcher());
+    let contract_a = network.a.instantiate(code_a, ADMIN, &msg).unwrap();
+    let code_b = network.b.store_code(dispatcher());
+    let contract_b = network.b.instantiate(code_b, ADMIN, &msg).unwrap();
+    let (channel_a, channel_b) = network
+        .open_channel(&contract_a, &contract_b, IBC_ORDER, IBC_VERSION)
+        .unwrap();
+    (network, contract_a, contract_b, channel_a, channel_b)
+}
+
+fn ping(channel: &str) -> ExecuteMsg {
+    ExecuteMsg::Ping {
+        channel: channel.to_string(),
+        payload: None,
+        expect_echo: None,
+    }
+}
+
+/// Pongs counted by `contract` on chain A.
+fn pongs(network: &Network, contract: &Addr, channel: &str) -> u32 {
+    let res: GetCounterResponse = network
+        .a
+        .query(
+            contract,
+            &QueryMsg::GetCounter {
+                channel: channel.to_string(),
+            },
+        )
+        .unwrap();
+    res.count
+}
+
+fn ack_of(delivery: &Delivery) -> Ack {
+    match delivery {
+        Delivery::Acked { ack, .. } => from_binary(ack).unwrap(),
+        Delivery::TimedOut { .. } => panic!("packet timed out"),
+    }
+}
+
+#[test]
+fn handshake() {
+    let (network, contract_a, contract_b, channel_a, channel_b) = setup();
+
+    let end_a = network.a.channel(&channel_a).unwrap();
+    assert_eq!(end_a.contract, contract_a);
+    assert_eq!(end_a.channel.counterparty_endpoint.channel_id, channel_b);
+    assert_eq!(
+        end_a.channel.counterparty_endpoint.port_id,
+        format!("wasm.{}", contract_b)
+    );
+    let end_b = network.b.channel(&channel_b).unwrap();
+    assert_eq!(end_b.channel.counterparty_endpoint.channel_id, channel_a);
+
+    // The dispatcher checks the version during the handshake.
+    let mut network = network;
+    let err = network
+        .open_channel(&contract_a, &contract_b, IBC_ORDER, "pong-1")
+        .unwrap_err();
+    assert!(matches!(err, HarnessError::Contract { .. }));
+}
+
+#[test]
+fn relay_ping() {
+    let (mut network, contract_a, _, channel_a, _) = setup();
+
+    let res = network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    assert_eq!(res.events_of("wasm-dispatcher_send").len(), 1);
+
+    let deliveries = network.relay().unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert!(matches!(ack_of(&deliveries[0]), Ack::Result(_)));
+    match &deliveries[0] {
+        Delivery::Acked { from, receive, .. } => {
+            assert_eq!(*from, Side::A);
+            assert_eq!(receive.events_of("wasm-dispatcher_receive").len(), 1);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(pongs(&network, &contract_a, &channel_a), 1);
+
+    // Nothing left to relay.
+    assert_eq!(network.relay().unwrap(), vec![]);
+}
+
+#[test]
+fn dropped_packet_times_out() {
+    let (mut network, contract_a, _, channel_a, _) = setup();
+
+    network.inject(Fault::Drop);
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    assert_eq!(network.relay().unwrap(), vec![]);
+    assert_eq!(network.pending(), 1);
+
+    // Packets live for five minutes.
+    network.advance_blocks(59);
+    assert_eq!(network.relay().unwrap(), vec![]);
+    network.advance_blocks(1);
+    let deliveries = network.relay().unwrap();
+    assert!(matches!(
+        deliveries[..],
+        [Delivery::TimedOut { from: Side::A, .. }]
+    ));
+    assert_eq!(network.pending(), 0);
+
+    let health: ChannelHealthResponse = network
+        .a
+        .query(
+            &contract_a,
+            &QueryMsg::GetChannelHealth {
+                channel: channel_a.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(health.consecutive_timeouts, 1);
+    assert_eq!(pongs(&network, &contract_a, &channel_a), 0);
+}
+
+#[test]
+fn delayed_packet() {
+    let (mut network, contract_a, _, channel_a, _) = setup();
+
+    network.inject(Fault::Delay { blocks: 3 });
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+
+    // Only the second ping arrives, the first one is still accepted once
+    // it does.
+    assert_eq!(network.relay().unwrap().len(), 1);
+    network.advance_blocks(2);
+    assert_eq!(network.relay().unwrap(), vec![]);
+    network.advance_blocks(1);
+    let deliveries = network.relay().unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert!(matches!(ack_of(&deliveries[0]), Ack::Result(_)));
+    assert_eq!(pongs(&network, &contract_a, &channel_a), 2);
+
+    // Delayed past its timeout it times out instead.
+    network.inject(Fault::Delay { blocks: 100 });
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    assert_eq!(network.relay().unwrap(), vec![]);
+    network.advance_blocks(60);
+    let deliveries = network.relay().unwrap();
+    assert!(matches!(deliveries[..], [Delivery::TimedOut { .. }]));
+}
+
+#[test]
+fn reordered_packets() {
+    let (mut network, contract_a, _, channel_a, _) = setup();
+
+    network.inject(Fault::Reorder);
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+
+    let deliveries = network.relay().unwrap();
+    let sequences: Vec<u64> = deliveries
+        .iter()
+        .map(|delivery| match delivery {
+            Delivery::Acked { packet, .. } => packet.sequence,
+            Delivery::TimedOut { .. } => panic!("packet timed out"),
+        })
+        .collect();
+    // The channel is unordered, the overtaken packet is still accepted.
+    assert_eq!(sequences, vec![2, 1]);
+    assert!(matches!(ack_of(&deliveries[0]), Ack::Result(_)));
+    assert!(matches!(ack_of(&deliveries[1]), Ack::Result(_)));
+    assert_eq!(pongs(&network, &contract_a, &channel_a), 2);
+}
+
+#[test]
+fn failing_submessage_is_rolled_back() {
+    let (mut network, contract_a, contract_b, channel_a, _) = setup();
+    let allow = ExecuteMsg::Admin(AdminMsg::SetAllowedContracts {
+        contracts: Some(vec!["nobody".to_string()]),
+    });
+    network.b.execute(&contract_b, ADMIN, &allow).unwrap();
+
+    let batch = |atomic| ExecuteMsg::Batch {
+        channel: channel_a.clone(),
+        msgs: vec![
+            IbcExecuteMsg::Ping {
+                payload: None,
+                expect_echo: false,
+            },
+            IbcExecuteMsg::Execute {
+                contract: "nobody".to_string(),
+                msg: to_binary(&"hello").unwrap(),
+            },
+        ],
+        atomic,
+        force: None,
+    };
+
+    // The execute submessage on chain B fails, only its own writes are
+    // reverted and the batch reports the failure, redacted as on wasmd.
+    network
+        .a
+        .execute(&contract_a, ADMIN, &batch(false))
+        .unwrap();
+    // An atomic batch is reverted as a whole, its ping is not counted.
+    network.a.execute(&contract_a, ADMIN, &batch(true)).unwrap();
+
+    let deliveries = network.relay().unwrap();
+    let results = |delivery| -> Vec<Ack> {
+        match ack_of(delivery) {
+            Ack::Result(data) => from_binary::<BatchResponse>(&data).unwrap().results,
+            Ack::Error(err) => panic!("batch failed: {}", err),
+        }
+    };
+    // `ErrNotFound`, "nobody" is no contract.
+    let error = Ack::Error("codespace: wasm, code: 8".to_string());
+    // The ping of the batch reports its own pong.
+    let pong: IbcPingResponse =
+        from_binary(&results(&deliveries[0])[0].clone().into_result().unwrap()).unwrap();
+    assert_eq!(pong.result, "pong");
+    assert_eq!(results(&deliveries[0])[1], error);
+    assert_eq!(results(&deliveries[1]), vec![error.clone(), error]);
+
+    let msg = ExecuteMsg::SyncCounter {
+        channel: channel_a.clone(),
+        force: None,
+    };
+    network.a.execute(&contract_a, ADMIN, &msg).unwrap();
+    network.relay().unwrap();
+    let query = QueryMsg::GetCounterSync { channel: channel_a };
+    let sync: CounterSyncResponse = network.a.query(&contract_a, &query).unwrap();
+    assert_eq!(sync.remote_count, Some(1));
+}
+
+#[test]
+fn example_contract() {
+    let mut network = Network::new("chain-a", "chain-b");
+    let msg = ibc_example::msg::InstantiateMsg {};
+    let code_a = network.a.store_code(example());
+    let contract_a = network.a.instantiate(code_a, ADMIN, &msg).unwrap();
+    let code_b = network.b.store_code(example());
+    let contract_b = network.b.instantiate(code_b, ADMIN, &msg).unwrap();
+    let (channel_a, channel_b) = network
+        .open_channel(
+            &contract_a,
+            &contract_b,
+            ibc_example::ibc::IBC_ORDER,
+            ibc_example::ibc::IBC_VERSION,
+        )
+        .unwrap();
+
+    let msg = ibc_example::msg::ExecuteMsg::Ping {
+        channel: channel_b.clone(),
+    };
+    network.b.execute(&contract_b, ADMIN, &msg).unwrap();
+    network.b.execute(&contract_b, ADMIN, &msg).unwrap();
+    let deliveries = network.relay().unwrap();
+    assert_eq!(deliveries.len(), 2);
+
+    let query = ibc_example::msg::QueryMsg::GetCounter {
+        channel: channel_b.clone(),
+    };
+    let res: ibc_example::msg::GetCounterResponse = network.b.query(&contract_b, &query).unwrap();
+    assert_eq!(res.count, 2);
+
+    // No packets are received on a closed channel, they time out.
+    network.b.execute(&contract_b, ADMIN, &msg).unwrap();
+    network.close_channel(Side::A, &channel_a).unwrap();
+    let deliveries = network.relay().unwrap();
+    assert!(matches!(
+        deliveries[..],
+        [Delivery::TimedOut { from: Side::B, .. }]
+    ));
+}
+
+#[test]
+fn sync_counter() {
+    let (mut network, contract_a, _, channel_a, _) = setup();
+
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    network
+        .a
+        .execute(&contract_a, ADMIN, &ping(&channel_a))
+        .unwrap();
+    let msg = ExecuteMsg::SyncCounter {
+        channel: channel_a.clone(),
+        force: None,
+    };
+    network.a.execute(&contract_a, ADMIN, &msg).unwrap();
+    network.relay().unwrap();
+
+    let query = QueryMsg::GetCounterSync {
+        channel: channel_a.clone(),
+    };
+    let sync: CounterSyncResponse = network.a.query(&contract_a, &query).unwrap();
+    assert_eq!(sync.local_count, 2);
+    assert_eq!(sync.remote_count, Some(2));
+    assert_eq!(sync.difference, Some(0));
+    assert_eq!(sync.synced_at, Some(network.a.block().time));
+}
@@ -66,7 +66,7 @@ test.serial("ping the remote chain", async (t) => {
   function showConnections(
     cosmwasm: CosmWasmSigner,
     contractAddr: string
-  ): Promise<{ connections: string[] }> {
+  ): Promise<{ connections: { channel: string; state: string }[] }> {
     const query = { get_connections: {} };
     return cosmwasm.sign.queryContractSmart(contractAddr, query);
   }
@@ -81,12 +81,14 @@ test.serial("ping the remote chain", async (t) => {
   t.is(wasmConnections.length, 1);
   t.is(osmoConnections.length, 1);
   assert(wasmConnections[0]);
-  t.is(wasmConnections[0], osmoConnections[0]);
+  t.is(wasmConnections[0].channel, osmoConnections[0].channel);
+  t.is(wasmConnections[0].state, "open");
+  t.is(osmoConnections[0].state, "open");
 
-  const channelId = wasmConnections[0];
+  const channelId = wasmConnections[0].channel;
 
-  t.log(`Wasm channel id: ${wasmConnections[0]}`);
-  t.log(`Osmo channel id: ${osmoConnections[0]}`);
+  t.log(`Wasm channel id: ${wasmConnections[0].channel}`);
+  t.log(`Osmo channel id: ${osmoConnections[0].channel}`);
   //Send msg with ping
   const msg = {
     ping: {